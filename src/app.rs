@@ -0,0 +1,1538 @@
+
+use std::collections::VecDeque;
+use std::convert::TryFrom;
+use std::fmt;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+use termion::event::{Key, MouseButton, MouseEvent};
+use tui::{
+    layout::Rect,
+    style::Color,
+    widgets::{Block, Borders},
+};
+
+use crate::cell;
+use crate::census::Census;
+use crate::cycle::CycleDetector;
+use crate::game::Simulation;
+use crate::grid::{Grid, Region};
+use crate::library;
+use crate::pattern::Pattern;
+use crate::random::Random;
+use crate::soup::Soup;
+use crate::timeline::{self, Change, ChangeKind, Timeline};
+use crate::universe::BoundingBox;
+use crate::viewport::{Marker, Outline, View, Viewport};
+
+// Generations skipped by the jump key when no count was typed before it
+pub const DEFAULT_JUMP: u64 = 100;
+
+// Limits of the tick rate, which is halved or doubled by the speed keys
+pub const MIN_TICK_RATE: Duration = Duration::from_millis(1);
+pub const MAX_TICK_RATE: Duration = Duration::from_secs(5);
+
+// Limit of the generations computed for every tick
+pub const MAX_GENERATIONS_PER_TICK: u64 = 1 << 16;
+
+// Largest selection that can be copied or changed, since its cells are held in a grid
+pub const MAX_SELECTION_CELLS: u64 = 1 << 24;
+
+// File the population history is exported to
+pub const HISTORY_FILE: &str = "population.csv";
+
+// File the census of objects is written to
+pub const CENSUS_FILE: &str = "census.txt";
+
+const MEGABYTE: f64 = (1 << 20) as f64;
+
+// Generations per second are averaged over this much time
+pub const RATE_WINDOW: Duration = Duration::from_secs(2);
+
+// Measures how fast generations go by
+#[derive(Debug, Clone, Default)]
+struct RateMeter {
+    samples: VecDeque<(Instant, u64)>
+}
+
+impl RateMeter {
+
+    fn record(&mut self, now: Instant, generation: u64) {
+
+        self.samples.push_back((now, generation));
+        while self.samples.len() > 2 && now.duration_since(self.samples[0].0) > RATE_WINDOW {
+            self.samples.pop_front();
+        }
+
+    }
+
+    fn rate(&self) -> f64 {
+
+        match (self.samples.front(), self.samples.back()) {
+            (Some((start, first)), Some((end, last))) if end > start => {
+                (last - first) as f64 / end.duration_since(*start).as_secs_f64()
+            },
+            _ => 0.0
+        }
+
+    }
+
+}
+
+// Changes made to a selection or the clipboard
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Transform {
+    Rotate,
+    FlipHorizontally,
+    FlipVertically,
+    Clear,
+    Fill,
+    Randomize
+}
+
+impl Transform {
+
+    // Whether the cells are only moved around, which makes sense for the clipboard too
+    fn moves_cells(&self) -> bool {
+        matches!(self, Transform::Rotate | Transform::FlipHorizontally | Transform::FlipVertically)
+    }
+
+    fn apply_to(&self, cells: &Grid) -> Grid {
+        match self {
+            Transform::Rotate => cells.rotated(),
+            Transform::FlipHorizontally => cells.flipped_horizontally(),
+            Transform::FlipVertically => cells.flipped_vertically(),
+            _ => cells.clone()
+        }
+    }
+
+}
+
+impl fmt::Display for Transform {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+
+        let verb = match self {
+            Transform::Rotate => "turned",
+            Transform::FlipHorizontally => "mirrored",
+            Transform::FlipVertically => "flipped",
+            Transform::Clear => "cleared",
+            Transform::Fill => "filled",
+            Transform::Randomize => "randomized"
+        };
+
+        write!(f, "{}", verb)
+
+    }
+}
+
+// What dragging the mouse does, decided by the button pressed
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Drag {
+    Paint(cell::CellState),
+    // where the drag started and the viewport origin at that time
+    Pan { anchor: (u16, u16), origin: (i64, i64) }
+}
+
+// State of the terminal app around the running simulation
+pub struct App {
+    pub area: Rect,
+    pub game: Box<dyn Simulation>,
+    pub viewport: Viewport,
+    cursor: (i64, i64),
+    drag: Option<Drag>,
+    rate: RateMeter,
+    timeline: Timeline,
+    cycle: CycleDetector,
+    // census shown in place of the population chart, taken when asked for
+    census: Option<Census>,
+    // soup the board was last filled with, and its size
+    soup: Soup,
+    soup_size: (usize, usize),
+    // pattern highlighted in the library picker, which is open while browsing
+    library_index: usize,
+    browsing: bool,
+    // corner of the selection opposite the cursor, and cells copied from a selection
+    anchor: Option<(i64, i64)>,
+    clipboard: Option<Grid>,
+    random: Random,
+    paused: bool,
+    tick_rate: Duration,
+    fastest: bool,
+    generations_per_tick: u64,
+    count: Option<u64>,
+    message: Option<String>,
+    quit: bool
+}
+
+impl App {
+
+    pub fn new(game: Box<dyn Simulation>) -> Self {
+
+        Self {
+            area: Rect::new(0, 0, 200, 200), // x, y, width, height layout
+            game,
+            viewport: Viewport::new(),
+            cursor: (0, 0),
+            drag: None,
+            rate: RateMeter::default(),
+            timeline: Timeline::default(),
+            cycle: CycleDetector::default(),
+            census: None,
+            soup: Soup::new(0),
+            soup_size: (64, 64),
+            library_index: 0,
+            browsing: false,
+            anchor: None,
+            clipboard: None,
+            random: Random::new(0),
+            paused: false,
+            tick_rate: Duration::from_millis(250),
+            fastest: false,
+            generations_per_tick: 1,
+            count: None,
+            message: None,
+            quit: false
+        }
+
+    }
+
+    pub fn with_tick_rate(mut self, tick_rate: Duration) -> Self {
+        self.tick_rate = tick_rate.max(MIN_TICK_RATE).min(MAX_TICK_RATE);
+        self
+    }
+
+    // Memory kept for undo and rewind, zero turns them off
+    pub fn with_history_budget(mut self, budget: usize) -> Self {
+        self.timeline = Timeline::new(budget);
+        self
+    }
+
+    // Soup used when reseeding, the next seed is used for the first one
+    pub fn with_soup(mut self, soup: Soup, size: (usize, usize)) -> Self {
+        self.soup = soup;
+        self.soup_size = size;
+        self.random = Random::new(soup.seed);
+        self
+    }
+
+    // Time between ticks the event loop should use, zero when running as fast as possible
+    pub fn tick_rate(&self) -> Duration {
+
+        if self.fastest {
+            Duration::from_secs(0)
+        } else {
+            self.tick_rate
+        }
+
+    }
+
+    pub fn generations_per_tick(&self) -> u64 {
+        self.generations_per_tick
+    }
+
+    // Takes the area the canvas is drawn in, borders included
+    pub fn resize(&mut self, area: Rect) {
+
+        self.area = area;
+        self.viewport.resize(Block::default().borders(Borders::ALL).inner(area));
+        self.viewport.follow(&self.cursor);
+
+    }
+
+    pub fn cursor(&self) -> (i64, i64) {
+        self.cursor
+    }
+
+    pub fn view(&self) -> View<'_> {
+        View { game: self.game.as_ref(), viewport: &self.viewport, color: Color::Cyan }
+    }
+
+    // Cells between the selection anchor and the cursor
+    pub fn selection(&self) -> Option<BoundingBox> {
+        self.anchor.map(|anchor| BoundingBox { min: anchor, max: anchor }.including(&self.cursor))
+    }
+
+    pub fn selection_outline(&self) -> Option<Outline<'_>> {
+        self.selection().map(|region| Outline { region, viewport: &self.viewport, color: Color::Magenta })
+    }
+
+    pub fn cursor_marker(&self) -> Marker<'_> {
+        Marker { position: self.cursor, viewport: &self.viewport, color: Color::Yellow }
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    pub fn should_quit(&self) -> bool {
+        self.quit
+    }
+
+    // Keys:
+    //   q            quit
+    //   space        pause or resume
+    //   n            advance one generation while paused
+    //   [count]g     jump ahead count generations, DEFAULT_JUMP without a count
+    //   esc          forget a count being typed and drop the selection
+    //   + / -        halve or double the time between ticks
+    //   f            toggle running as fast as possible
+    //   > / <        double or halve the generations computed for every tick
+    //   arrows, hjkl move the cursor, count cells at a time
+    //   enter, x     toggle the cell under the cursor
+    //   HJKL         pan a quarter of the view
+    //   i / o        zoom in or out
+    //   a            fit the whole pattern in view
+    //   c            center the view on the population
+    //   e            export the population history to HISTORY_FILE
+    //   O            show or hide a census of the objects on the board
+    //   w            write a census of the board to CENSUS_FILE
+    //   R            replace the board with the soup of the next seed
+    //   p            browse the pattern library, see on_picker_key
+    //   v            start a selection at the cursor, or drop it
+    //   y / d        copy or cut the selection
+    //   P            paste what was copied with its top left corner at the cursor
+    //   T            turn the selection a quarter clockwise, or what was copied without one
+    //   X / Y        mirror the selection left to right or top to bottom, or what was copied
+    //   D / F / Z    clear, fill or randomize the selection
+    //   u / ctrl-r   undo or redo the last step or edit
+    //   b            step back one generation
+    //   [count]r     rewind to generation count, as far back as possible without a count
+    pub fn on_key(&mut self, key: Key) {
+
+        if self.browsing {
+            self.on_picker_key(key);
+            return;
+        }
+
+        let steps = i64::try_from(self.count.unwrap_or(1)).unwrap_or(i64::MAX);
+        self.message = None;
+
+        match key {
+            Key::Char('q') => self.quit = true,
+            Key::Char(' ') => self.paused = !self.paused,
+            Key::Char('+') | Key::Char('=') => self.tick_rate = (self.tick_rate / 2).max(MIN_TICK_RATE),
+            Key::Char('-') | Key::Char('_') => self.tick_rate = (self.tick_rate * 2).min(MAX_TICK_RATE),
+            Key::Char('f') => self.fastest = !self.fastest,
+            Key::Char('>') | Key::Char('.') => {
+                self.generations_per_tick = (self.generations_per_tick * 2).min(MAX_GENERATIONS_PER_TICK);
+            },
+            Key::Char('<') | Key::Char(',') => self.generations_per_tick = (self.generations_per_tick / 2).max(1),
+            Key::Char('n') if self.paused => self.advance(1),
+            Key::Char(digit @ '0'..='9') => {
+                let value = digit.to_digit(10).unwrap_or(0) as u64;
+                self.count = Some(self.count.unwrap_or(0).saturating_mul(10).saturating_add(value));
+                return;
+            },
+            Key::Left | Key::Char('h') => self.move_cursor((-steps, 0)),
+            Key::Right | Key::Char('l') => self.move_cursor((steps, 0)),
+            Key::Up | Key::Char('k') => self.move_cursor((0, -steps)),
+            Key::Down | Key::Char('j') => self.move_cursor((0, steps)),
+            Key::Char('\n') | Key::Char('x') => self.toggle_cell(),
+            Key::Char('H') => self.pan((-1, 0)),
+            Key::Char('L') => self.pan((1, 0)),
+            Key::Char('K') => self.pan((0, -1)),
+            Key::Char('J') => self.pan((0, 1)),
+            Key::Char('i') => self.viewport.set_zoom(self.viewport.zoom() + 1),
+            Key::Char('o') => self.viewport.set_zoom(self.viewport.zoom() - 1),
+            Key::Char('a') => {
+                if let Some(region) = self.game.bounding_box() {
+                    self.viewport.fit(&region);
+                }
+            },
+            Key::Char('c') => {
+                if let Some(center) = self.population_center() {
+                    self.viewport.center_on(&center);
+                }
+            },
+            Key::Char('e') => self.export_history(Path::new(HISTORY_FILE)),
+            Key::Char('O') => {
+                self.census = match self.census {
+                    Some(_) => None,
+                    None => Some(Census::of_simulation(self.game.as_ref()))
+                };
+            },
+            Key::Char('w') => self.export_census(Path::new(CENSUS_FILE)),
+            Key::Char('R') => self.reseed(),
+            Key::Char('p') => self.browsing = true,
+            Key::Char('v') => self.anchor = match self.anchor {
+                Some(_) => None,
+                None => Some(self.cursor)
+            },
+            Key::Esc => self.anchor = None,
+            Key::Char('y') => self.copy(false),
+            Key::Char('d') => self.copy(true),
+            Key::Char('P') => self.paste(),
+            Key::Char('T') => self.transform(Transform::Rotate),
+            Key::Char('X') => self.transform(Transform::FlipHorizontally),
+            Key::Char('Y') => self.transform(Transform::FlipVertically),
+            Key::Char('D') => self.transform(Transform::Clear),
+            Key::Char('F') => self.transform(Transform::Fill),
+            Key::Char('Z') => self.transform(Transform::Randomize),
+            Key::Char('u') => self.undo(),
+            Key::Ctrl('r') => self.redo(),
+            Key::Char('b') => {
+                let generation = self.game.get_evolution().saturating_sub(1);
+                self.rewind(generation);
+            },
+            Key::Char('r') => self.rewind(self.count.unwrap_or(0)),
+            Key::Char('g') => {
+                let generations = self.count.unwrap_or(DEFAULT_JUMP);
+                self.advance(generations);
+            },
+            _ => ()
+        }
+
+        self.count = None;
+
+    }
+
+    // Keys while browsing the pattern library:
+    //   up / down, k / j  highlight the previous or next pattern
+    //   enter             place the pattern at the cursor and close the library
+    //   esc, p, q         close the library
+    fn on_picker_key(&mut self, key: Key) {
+
+        let count = library::PATTERNS.len();
+
+        match key {
+            Key::Up | Key::Char('k') => self.library_index = (self.library_index + count - 1) % count,
+            Key::Down | Key::Char('j') => self.library_index = (self.library_index + 1) % count,
+            Key::Char('\n') => {
+                self.browsing = false;
+                let entry = &library::PATTERNS[self.library_index];
+                match entry.pattern() {
+                    Ok(pattern) => self.stamp(&pattern),
+                    Err(error) => self.message = Some(format!("could not read {}: {}", entry.name, error))
+                }
+            },
+            Key::Esc | Key::Char('p') | Key::Char('q') => self.browsing = false,
+            _ => ()
+        }
+
+    }
+
+    // Mouse:
+    //   left button   press and drag to draw cells
+    //   right button  press and drag to erase cells
+    //   middle button press and drag to pan
+    //   wheel         zoom in and out
+    // Every terminal cell passed over is painted whole, since it shows several cells. The pattern
+    // library covers the board while it is open, so the mouse does nothing then
+    pub fn on_mouse(&mut self, mouse: MouseEvent) {
+
+        if self.browsing {
+            return;
+        }
+
+        match mouse {
+            MouseEvent::Press(MouseButton::Left, column, row) => {
+                self.drag = Some(Drag::Paint(cell::CellState::Alive));
+                self.paint(column, row, true);
+            },
+            MouseEvent::Press(MouseButton::Right, column, row) => {
+                self.drag = Some(Drag::Paint(cell::CellState::Dead));
+                self.paint(column, row, true);
+            },
+            MouseEvent::Press(MouseButton::Middle, column, row) => {
+                self.drag = Some(Drag::Pan { anchor: (column, row), origin: self.viewport.origin });
+            },
+            MouseEvent::Press(MouseButton::WheelUp, ..) => self.viewport.set_zoom(self.viewport.zoom() + 1),
+            MouseEvent::Press(MouseButton::WheelDown, ..) => self.viewport.set_zoom(self.viewport.zoom() - 1),
+            MouseEvent::Hold(column, row) => match self.drag {
+                Some(Drag::Paint(_)) => self.paint(column, row, false),
+                Some(Drag::Pan { anchor, origin }) => {
+                    // the cells under the pointer stay under it
+                    let dx = self.viewport.dots_to_cells((anchor.0 as i64 - column as i64) * 2);
+                    let dy = self.viewport.dots_to_cells((anchor.1 as i64 - row as i64) * 4);
+                    self.viewport.origin = (origin.0 + dx, origin.1 + dy);
+                },
+                None => ()
+            },
+            MouseEvent::Release(..) => self.drag = None
+        }
+
+    }
+
+    pub fn on_tick(&mut self) {
+
+        if !self.paused {
+            self.advance(self.generations_per_tick);
+        }
+
+        // paused ticks are recorded too, so the rate drops to zero
+        self.rate.record(Instant::now(), self.game.get_evolution());
+
+    }
+
+    // Current speed, such as "250 ms", "max" or "10 ms x4" when several generations run per tick
+    pub fn speed(&self) -> String {
+
+        let mut speed = if self.fastest {
+            String::from("max")
+        } else {
+            format!("{} ms", self.tick_rate.as_millis())
+        };
+
+        if self.generations_per_tick > 1 {
+            speed.push_str(&format!(" x{}", self.generations_per_tick));
+        }
+
+        speed
+
+    }
+
+    pub fn generations_per_second(&self) -> f64 {
+        self.rate.rate()
+    }
+
+    // Contents of the side panel, one statistic per line
+    pub fn stats(&self) -> String {
+
+        let (births, deaths) = match self.game.last_changes() {
+            Some(changes) => (changes.births.to_string(), changes.deaths.to_string()),
+            None => (String::from("-"), String::from("-"))
+        };
+
+        let cycle = match self.cycle.cycle() {
+            Some(cycle) if cycle.is_still() => format!("still at {}", cycle.start),
+            Some(cycle) => format!("p{} at {}", cycle.period, cycle.start),
+            None => String::from("-")
+        };
+
+        [
+            ("Generation", self.game.get_evolution().to_string()),
+            ("Population", self.game.population().to_string()),
+            ("Births", births),
+            ("Deaths", deaths),
+            ("Gens/sec", format!("{:.1}", self.generations_per_second())),
+            ("Rule", self.game.get_rule().to_string()),
+            ("Topology", self.game.topology_name().to_string()),
+            ("Cycle", cycle),
+            ("Undo", format!("{:.1}/{} MB", self.timeline.used() as f64 / MEGABYTE, self.timeline.budget() >> 20))
+        ].iter()
+            .map(|(label, value)| format!("{:<12}{}\n", label, value))
+            .collect()
+
+    }
+
+    // Coordinate readout shown under the canvas, followed by the outcome of the last command
+    pub fn status(&self) -> String {
+
+        let window = self.viewport.window();
+        let mut status = format!("cursor ({}, {})   view ({}, {}) to ({}, {})   zoom {}",
+            self.cursor.0, self.cursor.1, window.min.0, window.min.1, window.max.0, window.max.1,
+            self.viewport.scale());
+
+        if let Some(selection) = self.selection() {
+            status.push_str(&format!("   selected {}x{}", selection.width(), selection.height()));
+        }
+
+        if let Some(message) = &self.message {
+            status.push_str("   ");
+            status.push_str(message);
+        }
+
+        status
+
+    }
+
+    // Populations of the latest generations, for a chart that is width points wide
+    pub fn population_chart(&self, width: usize) -> Vec<u64> {
+        self.game.history().recent(width)
+    }
+
+    pub fn export_history(&mut self, path: &Path) {
+
+        self.message = Some(match self.game.history().save_csv(path) {
+            Ok(_) => format!("population history saved to {}", path.display()),
+            Err(error) => format!("could not save the population history: {}", error)
+        });
+
+    }
+
+    // Lines of the census panel, None while the population chart is shown
+    pub fn census(&self) -> Option<String> {
+
+        self.census.as_ref().map(|census| {
+            let mut lines = format!("gen {}, {} objects\n", census.generation, census.objects.len());
+            for entry in census.entries() {
+                lines.push_str(&format!("{:>4} {}\n", entry.count, entry.name.unwrap_or(&entry.code)));
+            }
+            lines
+        })
+
+    }
+
+    // Takes a census of the board as it is now and writes it out
+    pub fn export_census(&mut self, path: &Path) {
+
+        let census = Census::of_simulation(self.game.as_ref());
+        self.message = Some(match census.save_text(path) {
+            Ok(_) => format!("census of {} objects saved to {}", census.objects.len(), path.display()),
+            Err(error) => format!("could not save the census: {}", error)
+        });
+
+        if self.census.is_some() {
+            self.census = Some(census);
+        }
+
+    }
+
+    // Pattern highlighted in the library while it is open
+    pub fn picker(&self) -> Option<usize> {
+        if self.browsing { Some(self.library_index) } else { None }
+    }
+
+    pub fn title(&self) -> String {
+
+        let mut title = format!("Game of Life [{}]", self.speed());
+
+        if self.paused {
+            title.push_str(" [paused]");
+        }
+
+        if let Some(count) = self.count {
+            title.push_str(&format!(" [jump {}]", count));
+        }
+
+        title
+
+    }
+
+    // Moves the cursor, staying on the grid for engines with edges and keeping it in view
+    fn move_cursor(&mut self, offset: (i64, i64)) {
+
+        let mut cursor = (self.cursor.0.saturating_add(offset.0), self.cursor.1.saturating_add(offset.1));
+
+        if let Some(limits) = self.game.limits() {
+            cursor.0 = cursor.0.max(limits.min.0).min(limits.max.0);
+            cursor.1 = cursor.1.max(limits.min.1).min(limits.max.1);
+        }
+
+        self.cursor = cursor;
+        self.viewport.follow(&self.cursor);
+
+    }
+
+    // Moves the view by a quarter of its size in a direction
+    fn pan(&mut self, direction: (i64, i64)) {
+
+        let window = self.viewport.window();
+        let step = ((window.width() as i64 / 4).max(1), (window.height() as i64 / 4).max(1));
+        self.viewport.origin.0 += direction.0 * step.0;
+        self.viewport.origin.1 += direction.1 * step.1;
+
+    }
+
+    // Average position of the living cells
+    fn population_center(&self) -> Option<(i64, i64)> {
+
+        let cells = self.game.cells_in(&self.game.bounding_box()?);
+        let count = cells.len() as i128;
+        let sum = cells.iter().fold((0i128, 0i128), |sum, (x, y)| (sum.0 + *x as i128, sum.1 + *y as i128));
+
+        Some(((sum.0 / count) as i64, (sum.1 / count) as i64))
+
+    }
+
+    fn toggle_cell(&mut self) {
+
+        let state = if self.game.is_alive(&self.cursor) {
+            cell::CellState::Dead
+        } else {
+            cell::CellState::Alive
+        };
+
+        // the cursor never leaves a grid, but it can go further out than HashLife reaches
+        if self.game.set_state(&self.cursor, state).is_ok() {
+            self.cycle.reset();
+            let generation = self.game.get_evolution();
+            self.timeline.record(Change { kind: ChangeKind::Edit, cells: vec![self.cursor], from: generation, to: generation });
+        } else {
+            self.message = Some(String::from("the cursor is out of reach of the engine"));
+        }
+
+    }
+
+    // Pauses, since the simulation would otherwise step forward again right away
+    fn undo(&mut self) {
+
+        self.paused = true;
+        self.cycle.reset();
+        if self.timeline.undo(self.game.as_mut()).is_none() {
+            self.message = Some(String::from("nothing to undo"));
+        }
+
+    }
+
+    fn redo(&mut self) {
+
+        self.cycle.reset();
+        if self.timeline.redo(self.game.as_mut()).is_none() {
+            self.message = Some(String::from("nothing to redo"));
+        }
+
+    }
+
+    // Pauses and undoes changes until the simulation is back at a generation
+    fn rewind(&mut self, generation: u64) {
+
+        self.paused = true;
+        self.cycle.reset();
+        let current = self.game.get_evolution();
+        let reached = self.timeline.rewind_to(self.game.as_mut(), generation);
+
+        self.message = Some(if reached == current && generation < current {
+            String::from("no earlier generations are stored")
+        } else {
+            format!("rewound to generation {}", reached)
+        });
+
+    }
+
+    // Clears the board and fills it with the next soup, in the middle of a grid or from the
+    // origin of an unbounded universe. Can be undone like any edit
+    fn reseed(&mut self) {
+
+        self.soup = self.soup.next();
+
+        let (size, origin) = match self.game.limits() {
+            Some(limits) => {
+                let size = (self.soup_size.0.min(limits.width() as usize), self.soup_size.1.min(limits.height() as usize));
+                let origin = (
+                    limits.min.0 + (limits.width() as usize - size.0) as i64 / 2,
+                    limits.min.1 + (limits.height() as usize - size.1) as i64 / 2
+                );
+                (size, origin)
+            },
+            None => (self.soup_size, (0, 0))
+        };
+
+        let before = self.live_cells();
+        for position in before.iter() {
+            let _ = self.game.set_state(position, cell::CellState::Dead);
+        }
+        for (x, y) in self.soup.cells(size) {
+            let _ = self.game.set_state(&(origin.0 + x as i64, origin.1 + y as i64), cell::CellState::Alive);
+        }
+
+        let generation = self.game.get_evolution();
+        let cells = timeline::toggled(&before, &self.live_cells());
+        self.timeline.record(Change { kind: ChangeKind::Edit, cells, from: generation, to: generation });
+        self.cycle.reset();
+        self.message = Some(format!("soup {}", self.soup));
+
+    }
+
+    // Brings the cells of a pattern to life with its top left corner at the cursor. Cells that
+    // fall outside of a grid are left out, the whole stamp is undone as one edit
+    fn stamp(&mut self, pattern: &Pattern) {
+
+        let mut cells = Vec::new();
+        for (x, y) in pattern.cells.iter() {
+            let position = (self.cursor.0 + *x as i64, self.cursor.1 + *y as i64);
+            if !self.game.is_alive(&position) && self.game.set_state(&position, cell::CellState::Alive).is_ok() {
+                cells.push(position);
+            }
+        }
+
+        let generation = self.game.get_evolution();
+        self.timeline.record(Change { kind: ChangeKind::Edit, cells, from: generation, to: generation });
+        self.cycle.reset();
+
+        let name = pattern.name.as_deref().unwrap_or("pattern");
+        self.message = Some(format!("placed {} at ({}, {})", name, self.cursor.0, self.cursor.1));
+
+    }
+
+    // Cells of a region of the board as a grid, None when there are too many of them
+    fn grid_of(&self, region: &BoundingBox) -> Option<Grid> {
+
+        if region.width().saturating_mul(region.height()) > MAX_SELECTION_CELLS {
+            return None;
+        }
+
+        let mut copy = Grid::new((region.width() as usize, region.height() as usize));
+        for (x, y) in self.game.cells_in(region) {
+            let _ = copy.set_state(&((x - region.min.0) as usize, (y - region.min.1) as usize), cell::CellState::Alive);
+        }
+
+        Some(copy)
+
+    }
+
+    // Writes every cell of a grid to the board with its top left corner at the origin, as one
+    // edit. Cells past the edges of a bounded board are left out
+    fn write_grid(&mut self, source: &Grid, origin: (i64, i64)) {
+
+        let size = source.get_size();
+        let region = BoundingBox { min: origin, max: (origin.0 + size.0 as i64 - 1, origin.1 + size.1 as i64 - 1) };
+
+        let before = self.game.cells_in(&region);
+        for position in before.iter() {
+            let _ = self.game.set_state(position, cell::CellState::Dead);
+        }
+        for (x, y) in source.live_cells() {
+            let _ = self.game.set_state(&(origin.0 + x as i64, origin.1 + y as i64), cell::CellState::Alive);
+        }
+
+        let generation = self.game.get_evolution();
+        let cells = timeline::toggled(&before, &self.game.cells_in(&region));
+        self.timeline.record(Change { kind: ChangeKind::Edit, cells, from: generation, to: generation });
+        self.cycle.reset();
+
+    }
+
+    // Copies the selection to the clipboard and drops it, clearing its cells when cutting
+    fn copy(&mut self, cut: bool) {
+
+        let selection = match self.selection() {
+            Some(selection) => selection,
+            None => {
+                self.message = Some(String::from("nothing selected"));
+                return;
+            }
+        };
+
+        let copy = match self.grid_of(&selection) {
+            Some(copy) => copy,
+            None => {
+                self.message = Some(String::from("selection is too large"));
+                return;
+            }
+        };
+
+        if cut {
+            self.write_grid(&Grid::new(*copy.get_size()), selection.min);
+        }
+
+        self.message = Some(format!("{} {}x{}", if cut { "cut" } else { "copied" }, selection.width(), selection.height()));
+        self.clipboard = Some(copy);
+        self.anchor = None;
+
+    }
+
+    fn paste(&mut self) {
+
+        match self.clipboard.take() {
+            Some(clipboard) => {
+                self.write_grid(&clipboard, self.cursor);
+                self.message = Some(format!("pasted at ({}, {})", self.cursor.0, self.cursor.1));
+                self.clipboard = Some(clipboard);
+            },
+            None => self.message = Some(String::from("nothing to paste"))
+        }
+
+    }
+
+    // Changes the cells of the selection as one edit. Turning and mirroring change the clipboard
+    // instead when nothing is selected, so a copy can be turned before it is pasted
+    fn transform(&mut self, transform: Transform) {
+
+        let selection = match (self.selection(), &mut self.clipboard) {
+            (Some(selection), _) => selection,
+            (None, Some(clipboard)) if transform.moves_cells() => {
+                *clipboard = transform.apply_to(clipboard);
+                self.message = Some(format!("{} the clipboard", transform));
+                return;
+            },
+            _ => {
+                self.message = Some(String::from("nothing selected"));
+                return;
+            }
+        };
+
+        // a turned selection swaps its width and height, so the square holding both is changed
+        let side = selection.width().max(selection.height()) as i64;
+        let area = match transform {
+            Transform::Rotate => BoundingBox { min: selection.min, max: (selection.min.0 + side - 1, selection.min.1 + side - 1) },
+            _ => selection
+        };
+
+        let mut cells = match self.grid_of(&area) {
+            Some(cells) => cells,
+            None => {
+                self.message = Some(String::from("selection is too large"));
+                return;
+            }
+        };
+
+        let region = Region::new((0, 0), ((selection.width() - 1) as usize, (selection.height() - 1) as usize));
+        let changed = match transform {
+            Transform::Rotate => cells.rotate_region(&region),
+            Transform::FlipHorizontally => cells.flip_region_horizontally(&region).map(|_| region),
+            Transform::FlipVertically => cells.flip_region_vertically(&region).map(|_| region),
+            Transform::Clear => cells.fill_region(&region, cell::CellState::Dead).map(|_| region),
+            Transform::Fill => cells.fill_region(&region, cell::CellState::Alive).map(|_| region),
+            Transform::Randomize => cells.randomize_region(&region, self.soup.density, &mut self.random).map(|_| region)
+        };
+
+        // the grid was made to hold the changed region, so this does not fail
+        if let Ok(changed) = changed {
+            self.write_grid(&cells, area.min);
+            self.anchor = Some(area.min);
+            self.cursor = (area.min.0 + changed.size().0 as i64 - 1, area.min.1 + changed.size().1 as i64 - 1);
+            self.move_cursor((0, 0));
+            self.message = Some(format!("{} the selection", transform));
+        }
+
+    }
+
+    fn live_cells(&self) -> Vec<(i64, i64)> {
+
+        match self.game.bounding_box() {
+            Some(region) => self.game.cells_in(&region),
+            None => Vec::new()
+        }
+
+    }
+
+    // Draws or erases the cells under a terminal cell, termion positions start at 1. A drag is
+    // undone as a whole, so only its first terminal cell starts a new edit
+    fn paint(&mut self, column: u16, row: u16, new_edit: bool) {
+
+        let state = match self.drag {
+            Some(Drag::Paint(state)) => state,
+            _ => return
+        };
+
+        let cells = match self.viewport.cells_under(column.saturating_sub(1), row.saturating_sub(1)) {
+            Some(cells) => cells,
+            None => return
+        };
+
+        let mut changed = Vec::new();
+        for y in cells.min.1..=cells.max.1 {
+            for x in cells.min.0..=cells.max.0 {
+                // cells off the edge of a finite grid are left alone
+                let alive = state == cell::CellState::Alive;
+                if self.game.is_alive(&(x, y)) != alive && self.game.set_state(&(x, y), state).is_ok() {
+                    changed.push((x, y));
+                }
+            }
+        }
+
+        if !changed.is_empty() {
+            self.cycle.reset();
+        }
+
+        let generation = self.game.get_evolution();
+        if new_edit {
+            self.timeline.record(Change { kind: ChangeKind::Edit, cells: changed, from: generation, to: generation });
+        } else {
+            self.timeline.extend_edit(changed, generation);
+        }
+
+    }
+
+    fn advance(&mut self, generations: u64) {
+
+        let before = if self.timeline.is_enabled() { Some(self.live_cells()) } else { None };
+        let from = self.game.get_evolution();
+
+        // until the simulation is known to repeat, every generation is looked at to find out
+        // when it does. Long jumps, and engines that skip ahead faster than they step, go to the
+        // last generation at once and start the search over
+        self.cycle.observe(self.game.as_ref());
+        let stepped = !self.game.skips_ahead() && generations <= MAX_GENERATIONS_PER_TICK;
+        let result = if self.cycle.cycle().is_none() && stepped {
+            (0..generations).try_for_each(|_| {
+                self.game.update()?;
+                self.cycle.observe(self.game.as_ref());
+                Ok(())
+            })
+        } else {
+            self.game.advance(generations)
+        };
+
+        // the run stops where the engine could not go on and says why in the status line
+        match result {
+            Ok(_) => {
+                self.cycle.observe(self.game.as_ref());
+            },
+            Err(_) => {
+                self.paused = true;
+                self.message = Some(format!("could not step past generation {}, the pattern grew beyond what the \
+                    engine can hold", self.game.get_evolution()));
+            }
+        }
+
+        if let Some(before) = before {
+            let cells = timeline::toggled(&before, &self.live_cells());
+            self.timeline.record(Change { kind: ChangeKind::Step, cells, from, to: self.game.get_evolution() });
+        }
+
+    }
+
+}
+
+
+// tests
+#[cfg(test)]
+use crate::{game, grid, hashlife::HashLife, rule};
+
+#[cfg(test)]
+fn blinker_app() -> App {
+
+    let mut cell_grid = grid::Grid::new((8, 8));
+    for x in 2..5 {
+        cell_grid.set_state(&(x, 3), cell::CellState::Alive).unwrap();
+    }
+
+    App::new(Box::new(game::GameOfLife::new(cell_grid, rule::Rule::default())))
+
+}
+
+#[test]
+fn test_app_pause_and_step() {
+
+    let mut app = blinker_app();
+
+    app.on_tick();
+    assert_eq!(app.game.get_evolution(), 1);
+
+    // ticks do nothing while paused, n steps exactly once
+    app.on_key(Key::Char(' '));
+    assert!(app.is_paused());
+    assert_eq!(app.title(), "Game of Life [250 ms] [paused]");
+    app.on_tick();
+    app.on_key(Key::Char('n'));
+    assert_eq!(app.game.get_evolution(), 2);
+
+    app.on_key(Key::Char(' '));
+    assert!(!app.is_paused());
+    app.on_key(Key::Char('n'));
+    assert_eq!(app.game.get_evolution(), 2);
+
+    app.on_key(Key::Char('q'));
+    assert!(app.should_quit());
+
+}
+
+#[test]
+fn test_app_jump() {
+
+    let mut app = blinker_app();
+
+    app.on_key(Key::Char('2'));
+    app.on_key(Key::Char('5'));
+    assert_eq!(app.title(), "Game of Life [250 ms] [jump 25]");
+    app.on_key(Key::Char('g'));
+    assert_eq!(app.game.get_evolution(), 25);
+    assert_eq!(app.title(), "Game of Life [250 ms]");
+
+    app.on_key(Key::Char('g'));
+    assert_eq!(app.game.get_evolution(), 25 + DEFAULT_JUMP);
+
+    // a cancelled count is not used by the next jump
+    app.on_key(Key::Char('7'));
+    app.on_key(Key::Esc);
+    app.on_key(Key::Char('g'));
+    assert_eq!(app.game.get_evolution(), 25 + 2 * DEFAULT_JUMP);
+    assert_eq!(app.game.population(), 3);
+
+    // HashLife jumps straight to the end, the glider has moved a cell for every four generations
+    let mut hashlife = HashLife::new(rule::Rule::default()).unwrap();
+    for (x, y) in [(1, 0), (2, 1), (0, 2), (1, 2), (2, 2)].iter() {
+        hashlife.set_state(&(*x, *y), cell::CellState::Alive).unwrap();
+    }
+    let mut app = App::new(Box::new(hashlife));
+    for digit in "65536".chars() {
+        app.on_key(Key::Char(digit));
+    }
+    app.on_key(Key::Char('g'));
+    assert_eq!(app.game.get_evolution(), 65536);
+    assert_eq!(app.game.bounding_box(), Some(BoundingBox { min: (16384, 16384), max: (16386, 16386) }));
+
+    // undoing the jump puts the glider back where it was
+    app.on_key(Key::Char('u'));
+    assert_eq!(app.game.get_evolution(), 0);
+    assert!(app.game.is_alive(&(1, 0)));
+
+    // a glider flies further than HashLife can follow, the run stops with a message
+    let mut hashlife = HashLife::default();
+    for (x, y) in [(1, 0), (2, 1), (0, 2), (1, 2), (2, 2)].iter() {
+        hashlife.set_state(&(*x, *y), cell::CellState::Alive).unwrap();
+    }
+    let mut app = App::new(Box::new(hashlife));
+    for digit in "99999999999999999999".chars() {
+        app.on_key(Key::Char(digit));
+    }
+    app.on_key(Key::Char('g'));
+    assert!(app.is_paused());
+    assert!(app.message.as_deref().is_some_and(|message| message.starts_with("could not step past generation ")));
+
+}
+
+#[test]
+fn test_app_speed() {
+
+    let mut app = blinker_app().with_tick_rate(Duration::from_millis(100));
+    assert_eq!(app.speed(), "100 ms");
+
+    app.on_key(Key::Char('+'));
+    assert_eq!(app.tick_rate(), Duration::from_millis(50));
+    for _ in 0..20 {
+        app.on_key(Key::Char('-'));
+    }
+    assert_eq!(app.tick_rate(), MAX_TICK_RATE);
+
+    app.on_key(Key::Char('f'));
+    assert_eq!(app.tick_rate(), Duration::from_secs(0));
+    assert_eq!(app.speed(), "max");
+
+    // every tick runs the multiplied number of generations
+    app.on_key(Key::Char('>'));
+    app.on_key(Key::Char('>'));
+    assert_eq!(app.speed(), "max x4");
+    app.on_tick();
+    assert_eq!(app.game.get_evolution(), 4);
+
+    app.on_key(Key::Char('f'));
+    app.on_key(Key::Char('<'));
+    assert_eq!(app.title(), "Game of Life [5000 ms x2]");
+
+}
+
+#[test]
+fn test_app_cursor_editing() {
+
+    let mut app = blinker_app();
+    app.resize(Rect::new(0, 0, 4, 3));
+    assert_eq!(app.viewport.size(), (4, 4));
+
+    // turn the blinker into a block's worth of cells while paused
+    app.on_key(Key::Char(' '));
+    app.on_key(Key::Char('3'));
+    app.on_key(Key::Char('l'));
+    app.on_key(Key::Char('2'));
+    app.on_key(Key::Down);
+    app.on_key(Key::Char('x'));
+    assert_eq!(app.cursor(), (3, 2));
+    assert!(app.game.is_alive(&(3, 2)));
+    assert_eq!(app.game.population(), 4);
+
+    app.on_key(Key::Char('\n'));
+    assert!(!app.game.is_alive(&(3, 2)));
+
+    // the cursor stays on the 8x8 grid and in view
+    app.on_key(Key::Char('2'));
+    app.on_key(Key::Char('0'));
+    app.on_key(Key::Char('j'));
+    assert_eq!(app.cursor(), (3, 7));
+    assert_eq!(app.viewport.origin, (0, 4));
+
+    app.on_key(Key::Char('9'));
+    app.on_key(Key::Char('h'));
+    assert_eq!(app.cursor(), (0, 7));
+    assert_eq!(app.game.get_evolution(), 0);
+
+    // a count too large for a step still moves the right way
+    for digit in "99999999999999999999".chars() {
+        app.on_key(Key::Char(digit));
+    }
+    app.on_key(Key::Char('l'));
+    assert_eq!(app.cursor(), (7, 7));
+
+    // HashLife has no edges, but cells can be too far out for it
+    let mut app = App::new(Box::new(HashLife::default()));
+    let population = app.game.population();
+    for digit in "99999999999999999999".chars() {
+        app.on_key(Key::Char(digit));
+    }
+    app.on_key(Key::Char('l'));
+    app.on_key(Key::Char('x'));
+    assert_eq!(app.cursor(), (i64::MAX, 0));
+    assert_eq!(app.game.population(), population);
+    assert_eq!(app.message.as_deref(), Some("the cursor is out of reach of the engine"));
+
+}
+
+#[test]
+fn test_app_mouse_painting() {
+
+    let mut app = blinker_app();
+    app.resize(Rect::new(0, 0, 6, 4));
+    app.on_key(Key::Char(' '));
+
+    // the canvas starts inside the border, at terminal position (2, 2) counting from 1
+    app.on_mouse(MouseEvent::Press(MouseButton::Left, 2, 2));
+    assert_eq!(app.game.population(), 3 + 8);
+
+    // dragging keeps drawing, releasing stops
+    app.on_mouse(MouseEvent::Hold(3, 2));
+    assert_eq!(app.game.population(), 3 + 16 - 2);
+    app.on_mouse(MouseEvent::Release(3, 2));
+    app.on_mouse(MouseEvent::Hold(4, 2));
+    assert_eq!(app.game.population(), 3 + 16 - 2);
+
+    // erasing, the border and the edge of the 8x8 grid are ignored
+    app.on_mouse(MouseEvent::Press(MouseButton::Right, 1, 2));
+    app.on_mouse(MouseEvent::Hold(2, 2));
+    app.on_mouse(MouseEvent::Hold(3, 3));
+    assert_eq!(app.game.population(), 8 + 1);
+    assert!(app.game.is_alive(&(4, 3)));
+
+    // nothing is drawn under the open pattern library
+    app.on_key(Key::Char('p'));
+    app.on_mouse(MouseEvent::Press(MouseButton::Left, 2, 2));
+    app.on_mouse(MouseEvent::Hold(3, 3));
+    app.on_mouse(MouseEvent::Release(3, 3));
+    assert_eq!(app.game.population(), 8 + 1);
+    assert_eq!(app.picker(), Some(0));
+
+}
+
+#[test]
+fn test_app_viewport_controls() {
+
+    let mut app = blinker_app();
+    app.resize(Rect::new(0, 0, 10, 6));
+    assert_eq!(app.viewport.window().width(), 16);
+
+    // the blinker fills the view when fitted, zooming keeps it centered
+    app.on_key(Key::Char('a'));
+    assert_eq!(app.viewport.zoom(), 2);
+    assert_eq!(app.viewport.center(), (3, 3));
+    app.on_key(Key::Char('o'));
+    assert_eq!(app.viewport.zoom(), 1);
+    assert_eq!(app.viewport.center(), (3, 3));
+    app.on_mouse(MouseEvent::Press(MouseButton::WheelDown, 5, 5));
+    assert_eq!(app.viewport.zoom(), 0);
+
+    app.on_key(Key::Char('L'));
+    assert_eq!(app.viewport.center(), (7, 3));
+    app.on_key(Key::Char('c'));
+    assert_eq!(app.viewport.center(), (3, 3));
+
+    // dragging with the middle button moves the view with the pointer
+    let origin = app.viewport.origin;
+    app.on_mouse(MouseEvent::Press(MouseButton::Middle, 5, 5));
+    app.on_mouse(MouseEvent::Hold(3, 4));
+    assert_eq!(app.viewport.origin, (origin.0 + 4, origin.1 + 4));
+    app.on_mouse(MouseEvent::Release(3, 4));
+    assert_eq!(app.game.population(), 3);
+
+    assert_eq!(app.status(), format!("cursor (0, 0)   view ({}, {}) to ({}, {})   zoom 1:1",
+        origin.0 + 4, origin.1 + 4, origin.0 + 19, origin.1 + 19));
+
+}
+
+#[test]
+fn test_app_stats() {
+
+    let mut app = blinker_app();
+    assert_eq!(app.stats(), "Generation  0\nPopulation  3\nBirths      -\nDeaths      -\n\
+        Gens/sec    0.0\nRule        B3/S23\nTopology    torus\nCycle       -\nUndo        0.0/64 MB\n");
+
+    app.on_tick();
+    assert!(app.stats().starts_with("Generation  1\nPopulation  3\nBirths      2\nDeaths      2\n"));
+
+    // the blinker is back where it started after two generations
+    app.on_tick();
+    assert!(app.stats().contains("Cycle       p2 at 0\n"));
+
+    // editing forgets the cycle, it is found again while running
+    app.on_key(Key::Char('x'));
+    assert!(app.stats().contains("Cycle       -\n"));
+    app.on_key(Key::Char('x'));
+    for _ in 0..3 {
+        app.on_tick();
+    }
+    assert!(app.stats().contains("Cycle       p2 at 2\n"));
+
+}
+
+#[test]
+fn test_rate_meter() {
+
+    let start = Instant::now();
+    let mut meter = RateMeter::default();
+    assert_eq!(meter.rate(), 0.0);
+
+    for tick in 0..=10 {
+        meter.record(start + Duration::from_millis(tick * 100), tick * 4);
+    }
+    assert!((meter.rate() - 40.0).abs() < 1e-6);
+
+    // old samples fall out of the window, a pause brings the rate down
+    meter.record(start + Duration::from_millis(3000), 40);
+    meter.record(start + Duration::from_millis(5500), 40);
+    assert_eq!(meter.rate(), 0.0);
+
+}
+
+#[test]
+fn test_app_population_history() {
+
+    let mut app = blinker_app();
+    app.on_key(Key::Char('5'));
+    app.on_key(Key::Char('g'));
+    assert_eq!(app.population_chart(3), vec![3, 3, 3]);
+    assert_eq!(app.game.history().len(), 6);
+
+    let path = std::env::temp_dir().join(format!("game_of_life_history_{}.csv", std::process::id()));
+    app.export_history(&path);
+    assert!(app.status().ends_with(&format!("population history saved to {}", path.display())));
+    assert!(std::fs::read_to_string(&path).unwrap().starts_with("generation,population\n0,3\n1,3\n"));
+    std::fs::remove_file(&path).unwrap();
+
+    // the message goes away with the next key
+    app.on_key(Key::Char('l'));
+    assert!(!app.status().contains("saved"));
+
+}
+
+#[test]
+fn test_app_census() {
+
+    let mut app = blinker_app();
+    assert_eq!(app.census(), None);
+
+    // a block joins the blinker, the panel shows the census until asked to hide it
+    app.on_key(Key::Char(' '));
+    for position in [(6, 6), (7, 6), (6, 7), (7, 7)].iter() {
+        app.game.set_state(position, cell::CellState::Alive).unwrap();
+    }
+    app.on_key(Key::Char('O'));
+    assert_eq!(app.census(), Some(String::from("gen 0, 2 objects\n   1 blinker\n   1 block\n")));
+    app.on_key(Key::Char('O'));
+    assert_eq!(app.census(), None);
+
+    let path = std::env::temp_dir().join(format!("game_of_life_census_{}.txt", std::process::id()));
+    app.export_census(&path);
+    assert!(app.status().ends_with(&format!("census of 2 objects saved to {}", path.display())));
+    assert!(std::fs::read_to_string(&path).unwrap().contains("xs4_33"));
+    std::fs::remove_file(&path).unwrap();
+
+}
+
+#[test]
+fn test_app_reseed() {
+
+    let mut app = blinker_app().with_soup(Soup::new(9).with_density(0.5), (100, 4));
+    app.on_key(Key::Char(' '));
+
+    // the soup is as wide as the grid and four rows high, in the middle
+    app.on_key(Key::Char('R'));
+    assert_eq!(app.game.population(), Soup::new(10).cells((8, 4)).len());
+    assert!(app.live_cells().iter().all(|(_, y)| (2..6).contains(y)));
+    assert!(app.status().ends_with("soup seed 10, density 0.50, C1"));
+
+    app.on_key(Key::Char('R'));
+    assert_eq!(app.game.population(), Soup::new(11).cells((8, 4)).len());
+
+    // going back to the blinker
+    app.on_key(Key::Char('u'));
+    app.on_key(Key::Char('u'));
+    assert_eq!(app.game.population(), 3);
+    assert!(app.game.is_alive(&(2, 3)) && app.game.is_alive(&(4, 3)));
+
+}
+
+#[test]
+fn test_app_library_picker() {
+
+    let mut app = blinker_app();
+    app.on_key(Key::Char(' '));
+    app.on_key(Key::Char('5'));
+    app.on_key(Key::Char('l'));
+    assert_eq!(app.picker(), None);
+
+    // the picker wraps around and takes the keys while it is open
+    app.on_key(Key::Char('p'));
+    assert_eq!(app.picker(), Some(0));
+    app.on_key(Key::Up);
+    assert_eq!(app.picker(), Some(library::PATTERNS.len() - 1));
+    app.on_key(Key::Char('j'));
+    app.on_key(Key::Char('q'));
+    assert_eq!(app.picker(), None);
+    assert!(!app.should_quit());
+
+    // q only quits once the picker is closed
+    app.on_key(Key::Char('p'));
+    app.on_key(Key::Char('q'));
+    app.on_key(Key::Char('q'));
+    assert!(app.should_quit());
+    app.quit = false;
+
+    // a glider is placed at the cursor
+    app.on_key(Key::Char('p'));
+    app.on_key(Key::Char('\n'));
+    assert_eq!(app.picker(), None);
+    assert_eq!(app.game.population(), 3 + 5);
+    assert!(app.game.is_alive(&(6, 0)) && app.game.is_alive(&(7, 2)));
+    assert!(app.status().ends_with("placed Glider at (5, 0)"));
+
+    // a pattern too large for the grid is cut off, and undone as a single edit
+    app.on_key(Key::Char('p'));
+    app.on_key(Key::Char('k'));
+    app.on_key(Key::Char('\n'));
+    assert!(app.game.population() > 8);
+    app.on_key(Key::Char('u'));
+    assert_eq!(app.game.population(), 8);
+
+}
+
+#[test]
+fn test_app_selection() {
+
+    // the blinker is (2, 3) to (4, 3)
+    let mut app = blinker_app();
+    app.on_key(Key::Char(' '));
+    app.on_key(Key::Char('y'));
+    assert!(app.status().ends_with("nothing selected"));
+
+    // select from (2, 2) to (4, 3) and copy it
+    app.on_key(Key::Char('2'));
+    app.on_key(Key::Char('l'));
+    app.on_key(Key::Char('2'));
+    app.on_key(Key::Char('j'));
+    app.on_key(Key::Char('v'));
+    app.on_key(Key::Char('2'));
+    app.on_key(Key::Char('l'));
+    app.on_key(Key::Char('j'));
+    assert_eq!(app.selection(), Some(BoundingBox { min: (2, 2), max: (4, 3) }));
+    assert!(app.status().ends_with("selected 3x2"));
+    app.on_key(Key::Char('y'));
+    assert_eq!(app.selection(), None);
+    assert!(app.status().ends_with("copied 3x2"));
+
+    // pasting with the top left corner at the cursor overwrites what is there
+    app.on_key(Key::Char('j'));
+    app.on_key(Key::Char('P'));
+    assert_eq!(app.game.population(), 6);
+    assert!(app.game.is_alive(&(4, 5)) && app.game.is_alive(&(6, 5)));
+
+    // turned on the clipboard, then pasted upright
+    app.on_key(Key::Char('T'));
+    assert!(app.status().ends_with("turned the clipboard"));
+    app.on_key(Key::Char('h'));
+    app.on_key(Key::Char('P'));
+    assert!(app.game.is_alive(&(3, 4)) && app.game.is_alive(&(3, 6)));
+    app.on_key(Key::Char('u'));
+    assert_eq!(app.game.population(), 6);
+
+    // cutting a selection around the pasted blinker clears it
+    app.on_key(Key::Char('v'));
+    app.on_key(Key::Char('3'));
+    app.on_key(Key::Char('l'));
+    app.on_key(Key::Char('j'));
+    app.on_key(Key::Char('d'));
+    assert_eq!(app.game.population(), 3);
+    app.on_key(Key::Char('u'));
+    assert_eq!(app.game.population(), 6);
+
+    // esc drops a selection
+    app.on_key(Key::Char('v'));
+    app.on_key(Key::Esc);
+    assert_eq!(app.selection(), None);
+
+}
+
+#[test]
+fn test_app_selection_transforms() {
+
+    let mut app = blinker_app();
+    app.on_key(Key::Char(' '));
+
+    // the blinker turned in place stands upright from its left end
+    app.on_key(Key::Char('2'));
+    app.on_key(Key::Char('l'));
+    app.on_key(Key::Char('3'));
+    app.on_key(Key::Char('j'));
+    app.on_key(Key::Char('v'));
+    app.on_key(Key::Char('2'));
+    app.on_key(Key::Char('l'));
+    app.on_key(Key::Char('T'));
+    assert_eq!(app.selection(), Some(BoundingBox { min: (2, 3), max: (2, 5) }));
+    assert_eq!(app.live_cells(), vec![(2, 3), (2, 4), (2, 5)]);
+    app.on_key(Key::Char('u'));
+    assert_eq!(app.live_cells(), vec![(2, 3), (3, 3), (4, 3)]);
+
+    // mirrors of a 3x2 selection holding the right end of the blinker
+    app.on_key(Key::Esc);
+    app.on_key(Key::Char('2'));
+    app.on_key(Key::Char('k'));
+    app.on_key(Key::Char('l'));
+    app.on_key(Key::Char('v'));
+    app.on_key(Key::Char('2'));
+    app.on_key(Key::Char('l'));
+    app.on_key(Key::Char('j'));
+    app.on_key(Key::Char('X'));
+    assert_eq!(app.live_cells(), vec![(2, 3), (4, 3), (5, 3)]);
+    app.on_key(Key::Char('Y'));
+    assert_eq!(app.live_cells(), vec![(2, 3), (4, 4), (5, 4)]);
+
+    // fill, clear and randomize stay inside the selection
+    app.on_key(Key::Char('F'));
+    assert_eq!(app.game.population(), 7);
+    app.on_key(Key::Char('D'));
+    assert_eq!(app.game.population(), 1);
+    app.on_key(Key::Esc);
+    app.on_key(Key::Char('v'));
+    app.on_key(Key::Char('9'));
+    app.on_key(Key::Char('l'));
+    app.on_key(Key::Char('9'));
+    app.on_key(Key::Char('j'));
+    app.on_key(Key::Char('Z'));
+    assert_eq!(app.selection(), Some(BoundingBox { min: (5, 4), max: (7, 7) }));
+    assert!(app.game.population() > 1 && app.game.population() < 13);
+    assert!(app.live_cells().iter().all(|(x, y)| (*x, *y) == (2, 3) || (*x >= 5 && *y >= 4)));
+    assert!(app.status().ends_with("randomized the selection"));
+
+}
+
+#[test]
+fn test_app_undo_and_rewind() {
+
+    let mut app = blinker_app();
+    app.resize(Rect::new(0, 0, 6, 4));
+
+    // a few steps, a toggle and a drag
+    app.on_key(Key::Char('3'));
+    app.on_key(Key::Char('g'));
+    app.on_key(Key::Char(' '));
+    app.on_key(Key::Char('x'));
+    app.on_mouse(MouseEvent::Press(MouseButton::Left, 4, 3));
+    app.on_mouse(MouseEvent::Hold(5, 3));
+    app.on_mouse(MouseEvent::Release(5, 3));
+    assert_eq!(app.game.population(), 3 + 1 + 16);
+
+    // the drag and the toggle are undone separately, then the jump
+    app.on_key(Key::Char('u'));
+    assert_eq!(app.game.population(), 4);
+    app.on_key(Key::Char('u'));
+    assert_eq!(app.game.population(), 3);
+    app.on_key(Key::Ctrl('r'));
+    assert_eq!(app.game.population(), 4);
+    app.on_key(Key::Char('u'));
+    app.on_key(Key::Char('u'));
+    assert_eq!(app.game.get_evolution(), 0);
+    app.on_key(Key::Char('u'));
+    assert!(app.status().ends_with("nothing to undo"));
+
+    // stepping back one generation at a time, and rewinding further than what is stored
+    app.on_key(Key::Ctrl('r'));
+    for _ in 0..4 {
+        app.on_key(Key::Char('n'));
+    }
+    assert_eq!(app.game.get_evolution(), 7);
+    app.on_key(Key::Char('b'));
+    assert_eq!(app.game.get_evolution(), 6);
+    assert!(app.game.is_alive(&(2, 3)) && !app.game.is_alive(&(3, 2)));
+    app.on_key(Key::Char('5'));
+    app.on_key(Key::Char('r'));
+    assert_eq!(app.game.get_evolution(), 5);
+    assert!(app.status().ends_with("rewound to generation 5"));
+    app.on_key(Key::Char('r'));
+    assert_eq!(app.game.get_evolution(), 0);
+    assert_eq!(app.game.population(), 3);
+
+    // without a budget nothing can be undone
+    let mut app = blinker_app().with_history_budget(0);
+    app.on_tick();
+    app.on_key(Key::Char('u'));
+    assert_eq!(app.game.get_evolution(), 1);
+
+}
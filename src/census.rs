@@ -0,0 +1,558 @@
+
+use std::cmp::Ordering;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::sync::OnceLock;
+
+use crate::cell;
+use crate::game::Simulation;
+use crate::grid;
+use crate::rule::Rule;
+use crate::universe::Universe;
+
+// Longest period looked for when evolving an object on its own
+pub const MAX_PERIOD: u64 = 256;
+
+// Objects growing past this many cells on their own are left unclassified
+pub const MAX_OBJECT_POPULATION: usize = 1 << 12;
+
+// Living cells touching each other, diagonals included, belong to the same object
+const TOUCHING: i64 = 1;
+
+// Pieces that do not last on their own, such as the arms of a pulsar or the halves of a beacon,
+// are joined with the pieces this close to them
+const JOIN_DISTANCE: i64 = 2;
+
+const DIGITS: &[u8] = b"0123456789abcdefghijklmnopqrstuvwxyz";
+
+// Common objects of Conway's Game of Life, named in the census
+const KNOWN_OBJECTS: [(&str, &[&str]); 15] = [
+    ("block", &["OO", "OO"]),
+    ("beehive", &[".OO.", "O..O", ".OO."]),
+    ("loaf", &[".OO.", "O..O", ".O.O", "..O."]),
+    ("boat", &["OO.", "O.O", ".O."]),
+    ("ship", &["OO.", "O.O", ".OO"]),
+    ("tub", &[".O.", "O.O", ".O."]),
+    ("pond", &[".OO.", "O..O", "O..O", ".OO."]),
+    ("blinker", &["OOO"]),
+    ("toad", &[".OOO", "OOO."]),
+    ("beacon", &["OO..", "OO..", "..OO", "..OO"]),
+    ("pulsar", &["..OOO...OOO..", ".............", "O....O.O....O", "O....O.O....O", "O....O.O....O",
+        "..OOO...OOO..", ".............", "..OOO...OOO..", "O....O.O....O", "O....O.O....O", "O....O.O....O",
+        ".............", "..OOO...OOO.."]),
+    ("pentadecathlon", &["..O....O..", "OO.OOOO.OO", "..O....O.."]),
+    ("glider", &[".O.", "..O", "OOO"]),
+    ("LWSS", &[".O..O", "O....", "O...O", "OOOO."]),
+    ("MWSS", &["...O..", ".O...O", "O.....", "O....O", "OOOOO."])
+];
+
+// How an object behaves when left alone
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Kind {
+    StillLife,
+    Oscillator { period: u64 },
+    // moves by the displacement every period
+    Spaceship { period: u64, displacement: (i64, i64) },
+    // dies out, grows, or takes longer than MAX_PERIOD to repeat
+    Unclassified
+}
+
+impl fmt::Display for Kind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+
+        match self {
+            Kind::StillLife => write!(f, "still life"),
+            Kind::Oscillator { period } => write!(f, "oscillator p{}", period),
+            Kind::Spaceship { period, displacement } => {
+                let (dx, dy) = (displacement.0.abs(), displacement.1.abs());
+
+                // written as a fraction of the speed of light in lowest terms, 2c/4 is c/2
+                let (mut a, mut b) = (dx.max(dy) as u64, *period);
+                while b != 0 {
+                    (a, b) = (b, a % b);
+                }
+                let (speed, period) = (dx.max(dy) as u64 / a, period / a);
+                let direction = if dx == 0 || dy == 0 {
+                    "orthogonal"
+                } else if dx == dy {
+                    "diagonal"
+                } else {
+                    "oblique"
+                };
+
+                if speed == 1 {
+                    write!(f, "spaceship c/{} {}", period, direction)
+                } else {
+                    write!(f, "spaceship {}c/{} {}", speed, period, direction)
+                }
+            },
+            Kind::Unclassified => write!(f, "unclassified")
+        }
+
+    }
+}
+
+// A group of living cells on the board
+#[derive(Debug, Clone, PartialEq)]
+pub struct Object {
+    pub cells: Vec<(i64, i64)>,
+    pub kind: Kind,
+    // same for every orientation and phase of the object, in the style of apgcodes such as
+    // "xs4_33" for the block
+    pub code: String
+}
+
+impl Object {
+
+    // Name of the object if it is a common one
+    pub fn name(&self) -> Option<&'static str> {
+        known_objects().get(&self.code).copied()
+    }
+
+}
+
+// Number of objects sharing a code
+#[derive(Debug, Clone, PartialEq)]
+pub struct Entry {
+    pub code: String,
+    pub kind: Kind,
+    pub name: Option<&'static str>,
+    pub count: usize
+}
+
+// Objects making up a board
+#[derive(Debug, Clone, PartialEq)]
+pub struct Census {
+    pub generation: u64,
+    pub population: usize,
+    pub objects: Vec<Object>
+}
+
+impl Census {
+
+    // Splits living cells into objects and classifies each by evolving it on its own
+    pub fn of_cells(cells: &[(i64, i64)], rule: &Rule, generation: u64) -> Self {
+
+        Self {
+            generation,
+            population: cells.len(),
+            objects: split(cells, rule)
+        }
+
+    }
+
+    pub fn of_grid(cell_grid: &grid::Grid, rule: &Rule, generation: u64) -> Self {
+
+        let cells: Vec<(i64, i64)> = cell_grid.live_cells().iter().map(|(x, y)| (*x as i64, *y as i64)).collect();
+        Self::of_cells(&cells, rule, generation)
+
+    }
+
+    pub fn of_simulation(game: &dyn Simulation) -> Self {
+
+        let cells = match game.bounding_box() {
+            Some(region) => game.cells_in(&region),
+            None => Vec::new()
+        };
+
+        Self::of_cells(&cells, game.get_rule(), game.get_evolution())
+
+    }
+
+    // Objects grouped by code, most common first
+    pub fn entries(&self) -> Vec<Entry> {
+
+        let mut counts: HashMap<&str, Entry> = HashMap::new();
+        for object in self.objects.iter() {
+            counts.entry(&object.code)
+                .or_insert_with(|| Entry { code: object.code.clone(), kind: object.kind, name: object.name(), count: 0 })
+                .count += 1;
+        }
+
+        let mut entries: Vec<Entry> = counts.into_values().collect();
+        entries.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| compare_codes(&a.code, &b.code)));
+        entries
+
+    }
+
+    pub fn to_text(&self) -> String {
+
+        let entries = self.entries();
+        let width = entries.iter().map(|entry| entry.code.len()).max().unwrap_or(0).max(4) + 2;
+
+        let mut text = format!("generation {}, population {}, {} objects\n\n", self.generation, self.population,
+            self.objects.len());
+        text.push_str(&format!("{:>6}  {:<width$}{:<28}name\n", "count", "code", "kind", width = width));
+
+        for entry in entries {
+            text.push_str(&format!("{:>6}  {:<width$}{:<28}{}\n", entry.count, entry.code, entry.kind.to_string(),
+                entry.name.unwrap_or(""), width = width));
+        }
+
+        text
+
+    }
+
+    pub fn save_text(&self, path: &Path) -> io::Result<()> {
+        fs::write(path, self.to_text())
+    }
+
+}
+
+// Splits cells into classified objects, joining pieces that are unclassified on their own with
+// the pieces around them until that no longer changes anything
+fn split(cells: &[(i64, i64)], rule: &Rule) -> Vec<Object> {
+
+    let classified = |cells: Vec<(i64, i64)>| {
+        let (kind, code) = classify(&cells, rule);
+        Object { cells, kind, code }
+    };
+
+    let mut objects: Vec<Object> = components(cells, TOUCHING).into_iter().map(classified).collect();
+
+    loop {
+
+        // every object points to the first one it is joined with
+        let mut joined: Vec<usize> = (0..objects.len()).collect();
+        let root = |joined: &Vec<usize>, mut index: usize| {
+            while joined[index] != index {
+                index = joined[index];
+            }
+            index
+        };
+
+        for (index, object) in objects.iter().enumerate().filter(|(_, object)| object.kind == Kind::Unclassified) {
+            let near: HashSet<(i64, i64)> = object.cells.iter()
+                .flat_map(|(x, y)| (-JOIN_DISTANCE..=JOIN_DISTANCE)
+                    .flat_map(move |dy| (-JOIN_DISTANCE..=JOIN_DISTANCE).map(move |dx| (x + dx, y + dy))))
+                .collect();
+
+            for (other, _) in objects.iter().enumerate().filter(|(other, candidate)| *other != index
+                && candidate.cells.iter().any(|position| near.contains(position))) {
+                let (a, b) = (root(&joined, index), root(&joined, other));
+                joined[a.max(b)] = a.min(b);
+            }
+        }
+
+        let mut groups: Vec<(usize, Vec<Object>)> = Vec::new();
+        for (index, object) in objects.into_iter().enumerate() {
+            let first = root(&joined, index);
+            match groups.iter_mut().find(|(root, _)| *root == first) {
+                Some((_, group)) => group.push(object),
+                None => groups.push((first, vec![object]))
+            }
+        }
+
+        let changed = groups.iter().any(|(_, group)| group.len() > 1);
+        objects = groups.into_iter().map(|(_, mut group)| {
+            if group.len() == 1 {
+                group.remove(0)
+            } else {
+                let mut cells: Vec<(i64, i64)> = group.into_iter().flat_map(|object| object.cells).collect();
+                cells.sort_unstable();
+                classified(cells)
+            }
+        }).collect();
+
+        if !changed {
+            return objects;
+        }
+
+    }
+
+}
+
+// Groups cells that are at most a distance apart, counting diagonals
+fn components(cells: &[(i64, i64)], distance: i64) -> Vec<Vec<(i64, i64)>> {
+
+    let mut remaining: HashSet<(i64, i64)> = cells.iter().cloned().collect();
+    let mut groups = Vec::new();
+
+    for start in cells.iter() {
+
+        if !remaining.remove(start) {
+            continue;
+        }
+
+        let mut group = vec![*start];
+        let mut queue = VecDeque::from(vec![*start]);
+        while let Some((x, y)) = queue.pop_front() {
+            for dy in -distance..=distance {
+                for dx in -distance..=distance {
+                    if remaining.remove(&(x + dx, y + dy)) {
+                        group.push((x + dx, y + dy));
+                        queue.push_back((x + dx, y + dy));
+                    }
+                }
+            }
+        }
+
+        group.sort_unstable();
+        groups.push(group);
+
+    }
+
+    groups
+
+}
+
+// Evolves an object on its own until it comes back to its first shape, anywhere
+fn classify(cells: &[(i64, i64)], rule: &Rule) -> (Kind, String) {
+
+    let unclassified = (Kind::Unclassified, format!("unclassified_{}", cells.len()));
+
+    // the empty space around the object would come alive
+    if rule.is_born(0) {
+        return unclassified;
+    }
+
+    let (start, origin) = normalize(cells.to_vec());
+    let mut universe = Universe::new();
+    for position in cells.iter() {
+        universe.set_state(position, cell::CellState::Alive);
+    }
+
+    let mut phases = vec![start.clone()];
+    for generation in 1..=MAX_PERIOD {
+
+        universe = universe.step(rule);
+        if universe.population() == 0 || universe.population() > MAX_OBJECT_POPULATION {
+            return unclassified;
+        }
+
+        let (phase, corner) = normalize(universe.live_cells());
+        if phase == start {
+            let displacement = (corner.0 - origin.0, corner.1 - origin.1);
+            let kind = match (generation, displacement) {
+                (1, (0, 0)) => Kind::StillLife,
+                (period, (0, 0)) => Kind::Oscillator { period },
+                (period, displacement) => Kind::Spaceship { period, displacement }
+            };
+
+            return (kind, code(kind, &phases));
+        }
+
+        phases.push(phase);
+
+    }
+
+    unclassified
+
+}
+
+// Moves cells so their bounding box starts at the origin, sorted, along with the corner it started at
+fn normalize(mut cells: Vec<(i64, i64)>) -> (Vec<(i64, i64)>, (i64, i64)) {
+
+    let corner = cells.iter().fold((i64::MAX, i64::MAX), |corner, (x, y)| (corner.0.min(*x), corner.1.min(*y)));
+    for position in cells.iter_mut() {
+        *position = (position.0 - corner.0, position.1 - corner.1);
+    }
+
+    cells.sort_unstable();
+    (cells, corner)
+
+}
+
+type Transform = fn((i64, i64)) -> (i64, i64);
+
+// The eight rotations and reflections of a shape
+fn orientations(cells: &[(i64, i64)]) -> Vec<Vec<(i64, i64)>> {
+
+    let transforms: [Transform; 8] = [
+        |(x, y)| (x, y), |(x, y)| (-x, y), |(x, y)| (x, -y), |(x, y)| (-x, -y),
+        |(x, y)| (y, x), |(x, y)| (-y, x), |(x, y)| (y, -x), |(x, y)| (-y, -x)
+    ];
+
+    transforms.iter()
+        .map(|transform| normalize(cells.iter().map(|position| transform(*position)).collect()).0)
+        .collect()
+
+}
+
+// Shortest encoding over every phase and orientation, so the code does not depend on how the
+// object was found
+fn code(kind: Kind, phases: &[Vec<(i64, i64)>]) -> String {
+
+    let prefix = match kind {
+        Kind::StillLife => format!("xs{}", phases[0].len()),
+        Kind::Oscillator { period } => format!("xp{}", period),
+        Kind::Spaceship { period, .. } => format!("xq{}", period),
+        Kind::Unclassified => format!("unclassified_{}", phases[0].len())
+    };
+
+    let shape = phases.iter()
+        .flat_map(|phase| orientations(phase))
+        .map(|cells| encode(&cells))
+        .min_by(|a, b| compare_codes(a, b))
+        .unwrap_or_default();
+
+    format!("{}_{}", prefix, shape)
+
+}
+
+fn compare_codes(a: &str, b: &str) -> Ordering {
+    a.len().cmp(&b.len()).then_with(|| a.cmp(b))
+}
+
+// Extended Wechsler format: rows are taken five at a time, each column of a strip is a digit with
+// the top cell as the lowest bit, strips are separated by z and runs of empty columns are shortened
+fn encode(cells: &[(i64, i64)]) -> String {
+
+    let width = cells.iter().map(|(x, _)| x + 1).max().unwrap_or(0);
+    let height = cells.iter().map(|(_, y)| y + 1).max().unwrap_or(0);
+    let alive: HashSet<&(i64, i64)> = cells.iter().collect();
+
+    let strips: Vec<String> = (0..(height + 4) / 5).map(|strip| {
+
+        let columns: String = (0..width).map(|x| {
+            let bits = (0..5).filter(|row| alive.contains(&(x, strip * 5 + row))).fold(0, |bits, row| bits | 1 << row);
+            DIGITS[bits] as char
+        }).collect();
+
+        shorten_zeros(columns.trim_end_matches('0'))
+
+    }).collect();
+
+    strips.join("z")
+
+}
+
+fn shorten_zeros(columns: &str) -> String {
+
+    let mut shortened = String::new();
+    let mut zeros = 0;
+
+    let flush = |zeros: &mut usize, shortened: &mut String| {
+        while *zeros > 0 {
+            let run = (*zeros).min(39);
+            match run {
+                1 => shortened.push('0'),
+                2 => shortened.push('w'),
+                3 => shortened.push('x'),
+                _ => {
+                    shortened.push('y');
+                    shortened.push(DIGITS[run - 4] as char);
+                }
+            }
+            *zeros -= run;
+        }
+    };
+
+    for column in columns.chars() {
+        if column == '0' {
+            zeros += 1;
+        } else {
+            flush(&mut zeros, &mut shortened);
+            shortened.push(column);
+        }
+    }
+    flush(&mut zeros, &mut shortened);
+
+    shortened
+
+}
+
+// Codes of the common objects, worked out once
+fn known_objects() -> &'static HashMap<String, &'static str> {
+
+    static KNOWN: OnceLock<HashMap<String, &'static str>> = OnceLock::new();
+
+    KNOWN.get_or_init(|| {
+        KNOWN_OBJECTS.iter().map(|(name, rows)| {
+            let cells: Vec<(i64, i64)> = rows.iter().enumerate()
+                .flat_map(|(y, row)| row.chars().enumerate()
+                    .filter(|(_, state)| *state == 'O')
+                    .map(move |(x, _)| (x as i64, y as i64)))
+                .collect();
+            (classify(&cells, &Rule::default()).1, *name)
+        }).collect()
+    })
+
+}
+
+
+// tests
+#[cfg(test)]
+fn object_codes(census: &Census) -> Vec<(String, Kind, usize)> {
+    census.entries().into_iter().map(|entry| (entry.code, entry.kind, entry.count)).collect()
+}
+
+#[test]
+fn test_census_codes() {
+
+    let rule = Rule::default();
+
+    // the usual codes of a few objects, whatever their orientation
+    let block = Census::of_cells(&[(5, 5), (6, 5), (5, 6), (6, 6)], &rule, 0);
+    assert_eq!(block.objects[0].code, "xs4_33");
+    assert_eq!(block.objects[0].name(), Some("block"));
+
+    let blinker = Census::of_cells(&[(0, 0), (0, 1), (0, 2)], &rule, 0);
+    assert_eq!(blinker.objects[0].code, "xp2_7");
+    assert_eq!(blinker.objects[0].kind, Kind::Oscillator { period: 2 });
+
+    let glider = Census::of_cells(&[(10, 0), (9, 1), (11, 2), (10, 2), (9, 2)], &rule, 0);
+    assert_eq!(glider.objects[0].code, "xq4_153");
+    assert_eq!(glider.objects[0].kind.to_string(), "spaceship c/4 diagonal");
+    assert_eq!(glider.objects[0].name(), Some("glider"));
+
+    let beehive = Census::of_cells(&[(0, 1), (0, 2), (1, 0), (1, 3), (2, 1), (2, 2)], &rule, 0);
+    assert_eq!(beehive.objects[0].code, "xs6_696");
+    assert_eq!(beehive.objects[0].name(), Some("beehive"));
+
+    let lwss = Census::of_cells(&[(1, 0), (4, 0), (0, 1), (0, 2), (4, 2), (0, 3), (1, 3), (2, 3), (3, 3)], &rule, 0);
+    assert_eq!(lwss.objects[0].kind.to_string(), "spaceship c/2 orthogonal");
+    assert_eq!(lwss.objects[0].name(), Some("LWSS"));
+
+    assert_eq!(shorten_zeros("1000000000001"), "1y71");
+
+}
+
+#[test]
+fn test_census_table() {
+
+    let rule = Rule::default();
+
+    // two blocks, a blinker, a lone cell that dies and a pulsar, which is kept whole
+    let mut cells = vec![(0, 0), (1, 0), (0, 1), (1, 1), (20, 0), (21, 0), (20, 1), (21, 1), (10, 10), (11, 10),
+        (12, 10), (40, 40)];
+    for (y, row) in KNOWN_OBJECTS[10].1.iter().enumerate() {
+        for (x, state) in row.chars().enumerate() {
+            if state == 'O' {
+                cells.push((60 + x as i64, y as i64));
+            }
+        }
+    }
+
+    let census = Census::of_cells(&cells, &rule, 7);
+    assert_eq!(census.objects.len(), 5);
+    assert_eq!(object_codes(&census)[0], (String::from("xs4_33"), Kind::StillLife, 2));
+    assert_eq!(census.entries().iter().map(|entry| entry.name).collect::<Vec<_>>(),
+        vec![Some("block"), Some("blinker"), None, Some("pulsar")]);
+
+    let text = census.to_text();
+    assert!(text.starts_with("generation 7, population 60, 5 objects\n"));
+    assert!(text.lines().any(|line| line.split_whitespace().eq(["2", "xs4_33", "still", "life", "block"].iter().cloned())));
+    assert!(text.contains("unclassified_1 "));
+
+    // the halves of a beacon die out alone, they are joined again. A blinker close to a block
+    // lasts on its own, the two are counted apart
+    let beacon = Census::of_cells(&[(0, 0), (1, 0), (0, 1), (3, 2), (2, 3), (3, 3)], &rule, 0);
+    assert_eq!(beacon.objects.len(), 1);
+    assert_eq!(beacon.objects[0].name(), Some("beacon"));
+    let apart = Census::of_cells(&[(0, 0), (1, 0), (0, 1), (1, 1), (3, 0), (3, 1), (3, 2)], &rule, 0);
+    assert_eq!(apart.objects.len(), 2);
+
+    // the same objects on a grid
+    let mut cell_grid = grid::Grid::new((8, 8));
+    for position in [(1, 1), (2, 1), (1, 2), (2, 2), (5, 5), (5, 6), (5, 7)].iter() {
+        cell_grid.set_state(position, cell::CellState::Alive).unwrap();
+    }
+    let census = Census::of_grid(&cell_grid, &rule, 0);
+    assert_eq!(object_codes(&census), vec![(String::from("xp2_7"), Kind::Oscillator { period: 2 }, 1),
+        (String::from("xs4_33"), Kind::StillLife, 1)]);
+
+}
@@ -0,0 +1,605 @@
+
+use std::convert::TryFrom;
+use std::fmt;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::app::{MAX_TICK_RATE, MIN_TICK_RATE};
+use crate::cell;
+use crate::game::{self, Simulation};
+use crate::grid;
+use crate::hashlife::HashLife;
+use crate::library;
+use crate::pattern::{Format, Pattern, PatternError};
+use crate::random::Random;
+use crate::rule;
+use crate::soup::{Soup, Symmetry, DEFAULT_DENSITY};
+use crate::timeline;
+use crate::topology::{self, Topology};
+use crate::universe;
+
+pub const USAGE: &str = "Conway's Game of Life in the terminal
+
+USAGE:
+    game_of_life [OPTIONS]
+
+OPTIONS:
+    --width <CELLS>          Width of the grid [default: 100]
+    --height <CELLS>         Height of the grid [default: 200], at most 268435456 cells in all
+    --rule <RULE>            Rulestring such as B3/S23 or 23/36 [default: the pattern's rule, else B3/S23]
+    --pattern <FILE>         Pattern to load (.rle, .cells, .lif or .life), or a bundled one by name
+    --offset <X,Y>           Top left corner of the pattern [default: centered]
+    --topology <NAME>        bounded, torus, klein, cross-surface, mirror or unbounded [default: torus]
+    --engine <NAME>          cells or hashlife, which needs --topology unbounded [default: cells]
+    --tick-rate <MS>         Milliseconds between generations, at most 5000 [default: 250]
+    --fill <DENSITY>         Fill the grid with a random soup, each cell alive with this probability
+    --symmetry <NAME>        Symmetry of the soup: C1, C2, C4, D2, D4 or D8 [default: C1]
+    --soup-size <WxH>        Size of the soup, in the middle of the grid [default: the whole grid]
+    --seed <NUMBER>          Seed for the soup [default: from the clock]
+    --history-memory <MB>    Memory kept for undo and rewind, 0 turns them off [default: 64]
+    --threads <COUNT>        Threads stepping the grid in bands, or running soups with --search
+                             [default: 1 for a grid, one per core for a search]
+    --no-region-tracking     Step every cell of the grid, not only the tiles that changed lately
+    -h, --help               Print this message
+
+HEADLESS OPTIONS:
+    --headless               Run without the terminal interface and print a summary
+    --generations <COUNT>    Generations to run, the limit with --until-stable [default: 1000]
+    --until-stable           Stop early once the pattern is still or repeats itself
+    --output <FILE>          Write the final pattern to a file
+    --format <FORMAT>        rle, cells, life105 or life106 [default: from the file extension, else rle]
+
+SEARCH OPTIONS:
+    --search <SOUPS>         Run this many soups until they settle and report the objects they leave
+
+A search starts from --seed and counts up, with 16x16 soups at density 0.5 unless --fill,
+--symmetry or --soup-size are given. --generations limits every soup [default: 10000] and
+--output saves the report.
+
+Bundled patterns are glider, lwss, mwss, hwss, pulsar, pentadecathlon, r-pentomino, acorn,
+diehard, gosper-glider-gun, simkin-glider-gun, block-laying-switch-engine, infinite-growth-5x5
+and infinite-growth-one-cell-high. A file with the same name is loaded instead.
+
+Without --pattern or a soup the grid starts with Gosper's glider gun. --symmetry and
+--soup-size make a soup at density 0.5 when --fill is not given.
+
+Headless runs exit with 0 when done, 1 on errors, 2 on invalid options and 3 when
+--until-stable reached the generation limit first.
+";
+
+// Cells a grid given on the command line may hold, 32 MiB of cell state
+pub const MAX_GRID_CELLS: usize = 1 << 28;
+
+// Generations a headless run goes through when no count is given
+pub const DEFAULT_GENERATIONS: u64 = 1000;
+
+#[derive(Debug)]
+pub enum CliError {
+    UnknownFlag(String),
+    MissingValue(String),
+    InvalidValue { flag: String, value: String, expected: &'static str },
+    InvalidRule(rule::RuleParseError),
+    Pattern(PatternError),
+    UnboundedBirthOnZero,
+    HashLifeRequiresUnbounded,
+    RequiresHeadless(String),
+    // the engine could not compute the generation after this one
+    SteppingFailed { generation: u64 }
+}
+
+impl fmt::Display for CliError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CliError::UnknownFlag(flag) => write!(f, "Unknown option '{}', see --help", flag),
+            CliError::MissingValue(flag) => write!(f, "Missing value for {}", flag),
+            CliError::InvalidValue { flag, value, expected } => {
+                write!(f, "Invalid value '{}' for {}, expected {}", value, flag, expected)
+            },
+            CliError::InvalidRule(error) => write!(f, "Invalid rule: {}", error),
+            CliError::Pattern(error) => write!(f, "{}", error),
+            CliError::UnboundedBirthOnZero => write!(f, "Rules with B0 can not run on an unbounded universe"),
+            CliError::HashLifeRequiresUnbounded => write!(f, "--engine hashlife only runs with --topology unbounded"),
+            CliError::RequiresHeadless(flag) => write!(f, "{} only applies to --headless runs", flag),
+            CliError::SteppingFailed { generation } => {
+                write!(f, "Could not step past generation {}, the pattern grew beyond what the engine can hold", generation)
+            }
+        }
+    }
+}
+
+impl std::error::Error for CliError {}
+
+impl From<rule::RuleParseError> for CliError {
+    fn from(error: rule::RuleParseError) -> Self {
+        CliError::InvalidRule(error)
+    }
+}
+
+impl From<PatternError> for CliError {
+    fn from(error: PatternError) -> Self {
+        CliError::Pattern(error)
+    }
+}
+
+
+// Where the cells live: a finite grid glued together by a topology, or an unbounded universe
+#[derive(Debug, Clone)]
+pub enum Space {
+    Grid(Arc<dyn Topology>),
+    Unbounded
+}
+
+// Engine stepping an unbounded universe. HashLife is much faster on large regular patterns and
+// long jumps, the cell by cell engine on small chaotic ones
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Engine {
+    Cells,
+    HashLife
+}
+
+#[derive(Debug, Clone)]
+pub struct Options {
+    pub size: (usize, usize),
+    pub rule: Option<rule::Rule>,
+    pub pattern: Option<PathBuf>,
+    pub offset: Option<(usize, usize)>,
+    pub space: Space,
+    pub engine: Engine,
+    pub tick_rate: Duration,
+    pub fill: Option<f64>,
+    pub symmetry: Option<Symmetry>,
+    pub soup_size: Option<(usize, usize)>,
+    pub seed: Option<u64>,
+    pub history_budget: usize,
+    pub region_tracking: bool,
+    pub headless: bool,
+    pub generations: Option<u64>,
+    pub until_stable: bool,
+    pub output: Option<PathBuf>,
+    pub format: Option<Format>,
+    pub search: Option<u64>,
+    pub threads: Option<usize>
+}
+
+impl Default for Options {
+    fn default() -> Self {
+
+        Self {
+            size: (100, 200),
+            rule: None,
+            pattern: None,
+            offset: None,
+            space: Space::Grid(Arc::new(topology::Torus)),
+            engine: Engine::Cells,
+            tick_rate: Duration::from_millis(250),
+            fill: None,
+            symmetry: None,
+            soup_size: None,
+            seed: None,
+            history_budget: timeline::DEFAULT_BUDGET,
+            region_tracking: true,
+            headless: false,
+            generations: None,
+            until_stable: false,
+            output: None,
+            format: None,
+            search: None,
+            threads: None
+        }
+
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum Command {
+    Run(Box<Options>),
+    Help
+}
+
+// Parses the arguments that follow the program name. Values can be given as `--flag value` or
+// `--flag=value`
+pub fn parse<I>(args: I) -> Result<Command, CliError>
+where
+    I: IntoIterator<Item = String>
+{
+
+    let mut options = Options::default();
+    let mut args = args.into_iter();
+
+    while let Some(arg) = args.next() {
+
+        match arg.as_str() {
+            "-h" | "--help" => return Ok(Command::Help),
+            "--headless" => {
+                options.headless = true;
+                continue;
+            },
+            "--until-stable" => {
+                options.until_stable = true;
+                continue;
+            },
+            "--no-region-tracking" => {
+                options.region_tracking = false;
+                continue;
+            },
+            _ => ()
+        }
+
+        let (flag, inline_value) = match arg.find('=') {
+            Some(index) if arg.starts_with("--") => (arg[..index].to_string(), Some(arg[index + 1..].to_string())),
+            _ => (arg.clone(), None)
+        };
+
+        if !matches!(flag.as_str(), "--width" | "--height" | "--rule" | "--pattern" | "--offset" | "--topology" | "--engine"
+            | "--tick-rate" | "--fill" | "--symmetry" | "--soup-size" | "--seed" | "--history-memory" | "--generations"
+            | "--output" | "--format" | "--search" | "--threads") {
+            return Err(CliError::UnknownFlag(arg));
+        }
+
+        let value = match inline_value.or_else(|| args.next()) {
+            Some(value) => value,
+            None => return Err(CliError::MissingValue(flag))
+        };
+
+        let invalid = |expected| CliError::InvalidValue { flag: flag.clone(), value: value.clone(), expected };
+
+        match flag.as_str() {
+            "--width" => options.size.0 = parse_positive(&value).ok_or_else(|| invalid("a positive number of cells"))?,
+            "--height" => options.size.1 = parse_positive(&value).ok_or_else(|| invalid("a positive number of cells"))?,
+            "--rule" => options.rule = Some(value.parse()?),
+            "--pattern" => options.pattern = Some(PathBuf::from(&value)),
+            "--offset" => options.offset = Some(parse_offset(&value).ok_or_else(|| invalid("two numbers as X,Y"))?),
+            "--topology" => {
+                options.space = match value.as_str() {
+                    "unbounded" => Space::Unbounded,
+                    name => Space::Grid(topology::from_name(name)
+                        .ok_or_else(|| invalid("bounded, torus, klein, cross-surface, mirror or unbounded"))?)
+                };
+            },
+            "--engine" => {
+                options.engine = match value.as_str() {
+                    "cells" => Engine::Cells,
+                    "hashlife" => Engine::HashLife,
+                    _ => return Err(invalid("cells or hashlife"))
+                };
+            },
+            "--tick-rate" => {
+                let milliseconds = parse_positive(&value).ok_or_else(|| invalid("a positive number of milliseconds"))?;
+                let tick_rate = Duration::from_millis(milliseconds as u64);
+                if !(MIN_TICK_RATE..=MAX_TICK_RATE).contains(&tick_rate) {
+                    return Err(invalid("at most 5000 milliseconds"));
+                }
+                options.tick_rate = tick_rate;
+            },
+            "--fill" => {
+                match value.parse::<f64>() {
+                    Ok(density) if (0.0..=1.0).contains(&density) => options.fill = Some(density),
+                    _ => return Err(invalid("a density between 0 and 1"))
+                }
+            },
+            "--symmetry" => options.symmetry = Some(value.parse().map_err(|_| invalid("C1, C2, C4, D2, D4 or D8"))?),
+            "--soup-size" => options.soup_size = Some(parse_size(&value).ok_or_else(|| invalid("two positive numbers as WxH"))?),
+            "--seed" => options.seed = Some(value.parse().map_err(|_| invalid("a whole number"))?),
+            "--history-memory" => {
+                let megabytes: usize = value.parse().map_err(|_| invalid("a whole number of megabytes"))?;
+                options.history_budget = megabytes.checked_mul(1 << 20).ok_or_else(|| invalid("a smaller number of megabytes"))?;
+            },
+            "--generations" => options.generations = Some(value.parse().map_err(|_| invalid("a whole number"))?),
+            "--output" => options.output = Some(PathBuf::from(&value)),
+            "--format" => options.format = Some(value.parse().map_err(|_| invalid("rle, cells, life105 or life106"))?),
+            "--search" => options.search = Some(parse_positive(&value).ok_or_else(|| invalid("a positive number of soups"))? as u64),
+            "--threads" => options.threads = Some(parse_positive(&value).ok_or_else(|| invalid("a positive number of threads"))?),
+            _ => unreachable!()
+        }
+
+    }
+
+    if options.size.0.checked_mul(options.size.1).is_none_or(|cells| cells > MAX_GRID_CELLS) {
+        return Err(CliError::InvalidValue {
+            flag: "--width and --height".to_string(),
+            value: format!("{}x{}", options.size.0, options.size.1),
+            expected: "a grid of at most 268435456 cells"
+        });
+    }
+
+    if options.engine == Engine::HashLife && !matches!(options.space, Space::Unbounded) {
+        return Err(CliError::HashLifeRequiresUnbounded);
+    }
+
+    if !options.headless {
+        let batch_flags = [
+            ("--generations", options.generations.is_some() && options.search.is_none()),
+            ("--until-stable", options.until_stable),
+            ("--output", options.output.is_some() && options.search.is_none()),
+            ("--format", options.format.is_some())
+        ];
+        if let Some((flag, _)) = batch_flags.iter().find(|(_, given)| *given) {
+            return Err(CliError::RequiresHeadless(flag.to_string()));
+        }
+    }
+
+    Ok(Command::Run(Box::new(options)))
+
+}
+
+fn parse_positive(value: &str) -> Option<usize> {
+    value.parse().ok().filter(|number| *number > 0)
+}
+
+fn parse_size(value: &str) -> Option<(usize, usize)> {
+
+    let mut parts = value.split('x').map(parse_positive);
+    match (parts.next(), parts.next(), parts.next()) {
+        (Some(Some(width)), Some(Some(height)), None) => Some((width, height)),
+        _ => None
+    }
+
+}
+
+fn parse_offset(value: &str) -> Option<(usize, usize)> {
+
+    let mut parts = value.split(',').map(|part| part.trim().parse::<usize>());
+    match (parts.next(), parts.next(), parts.next()) {
+        (Some(Ok(x)), Some(Ok(y)), None) => Some((x, y)),
+        _ => None
+    }
+
+}
+
+impl Options {
+
+    // Random soup asked for, if any. Without a seed a new one is picked every time
+    pub fn soup(&self) -> Option<Soup> {
+
+        if self.fill.is_none() && self.symmetry.is_none() && self.soup_size.is_none() {
+            return None;
+        }
+
+        Some(Soup::new(self.seed.unwrap_or_else(Random::seed_from_time))
+            .with_density(self.fill.unwrap_or(DEFAULT_DENSITY))
+            .with_symmetry(self.symmetry.unwrap_or(Symmetry::C1)))
+
+    }
+
+    // Size of the soup, the whole board unless given
+    pub fn soup_size(&self) -> (usize, usize) {
+        self.soup_size.unwrap_or(self.size)
+    }
+
+    // Builds the simulation described by the options, loading the pattern file if there is one.
+    // A bundled pattern is used when no file has the name given
+    pub fn build(&self) -> Result<Box<dyn Simulation>, CliError> {
+
+        let soup = self.soup();
+        let pattern = match &self.pattern {
+            Some(path) => match library::find(&path.to_string_lossy()) {
+                Some(entry) if !path.exists() => Some(entry.pattern()?),
+                _ => Some(Pattern::load(path)?)
+            },
+            None if soup.is_none() => Some(Pattern::from_grid(&game::GameOfLife::default().cell_grid)),
+            None => None
+        };
+
+        let rule = self.rule
+            .or_else(|| pattern.as_ref().and_then(|pattern| pattern.rule))
+            .unwrap_or_default();
+
+        // centered by default, except for the demo glider gun which keeps its usual spot
+        let offset = self.offset.unwrap_or_else(|| match (&self.pattern, &pattern) {
+            (Some(_), Some(pattern)) => (
+                self.size.0.saturating_sub(pattern.size.0) / 2,
+                self.size.1.saturating_sub(pattern.size.1) / 2
+            ),
+            _ => (20, 23)
+        });
+
+        let soup_size = self.soup_size();
+
+        match &self.space {
+
+            Space::Grid(topology) => {
+
+                let mut cell_grid = grid::Grid::with_topology(self.size, topology.clone());
+
+                if let Some(soup) = &soup {
+                    let soup_offset = (self.size.0.saturating_sub(soup_size.0) / 2, self.size.1.saturating_sub(soup_size.1) / 2);
+                    soup.fill(&mut cell_grid, soup_offset, soup_size).map_err(|_| PatternError::DoesNotFit)?;
+                }
+
+                if let Some(pattern) = &pattern {
+                    pattern.place(&mut cell_grid, offset)?;
+                }
+
+                let game = game::GameOfLife::new(cell_grid, rule)
+                    .with_threads(self.threads.unwrap_or(1))
+                    .with_region_tracking(self.region_tracking);
+
+                Ok(Box::new(game))
+
+            },
+
+            Space::Unbounded => {
+
+                if rule.is_born(0) {
+                    return Err(CliError::UnboundedBirthOnZero);
+                }
+
+                let mut universe = universe::Universe::new();
+
+                if let Some(soup) = &soup {
+                    for (x, y) in soup.cells(soup_size) {
+                        universe.set_state(&(x as i64, y as i64), cell::CellState::Alive);
+                    }
+                }
+
+                if let Some(pattern) = &pattern {
+                    let coordinate = |offset: usize, cell: usize| offset.checked_add(cell)
+                        .and_then(|coordinate| i64::try_from(coordinate).ok())
+                        .ok_or(PatternError::DoesNotFit);
+                    for (x, y) in pattern.cells.iter() {
+                        let position = (coordinate(offset.0, *x)?, coordinate(offset.1, *y)?);
+                        universe.set_state(&position, cell::CellState::Alive);
+                    }
+                }
+
+                match self.engine {
+                    Engine::Cells => Ok(Box::new(game::UnboundedLife::new(universe, rule))),
+                    // B0 was ruled out above, only cells out of HashLife's reach are left to fail
+                    Engine::HashLife => Ok(Box::new(HashLife::from_universe(&universe, rule)
+                        .map_err(|_| PatternError::DoesNotFit)?))
+                }
+
+            }
+
+        }
+
+    }
+
+}
+
+
+// tests
+#[cfg(test)]
+fn run(args: &[&str]) -> Result<Command, CliError> {
+    parse(args.iter().map(|arg| arg.to_string()))
+}
+
+#[cfg(test)]
+fn options(args: &[&str]) -> Options {
+    match run(args) {
+        Ok(Command::Run(options)) => *options,
+        other => panic!("expected options, got {:?}", other)
+    }
+}
+
+#[test]
+fn test_cli_parse() {
+
+    assert!(matches!(run(&["--width", "5", "--help"]), Ok(Command::Help)));
+
+    let parsed = options(&["--width", "64", "--height=32", "--rule", "B36/S23", "--offset", "3,4",
+        "--topology", "klein", "--tick-rate", "50", "--fill", "0.25", "--seed", "7", "--pattern", "gun.rle", "--history-memory", "8"]);
+
+    assert_eq!(parsed.size, (64, 32));
+    assert_eq!(parsed.rule, Some("B36/S23".parse().unwrap()));
+    assert_eq!(parsed.offset, Some((3, 4)));
+    assert!(matches!(&parsed.space, Space::Grid(topology) if topology.name() == "klein"));
+    assert_eq!(parsed.tick_rate, Duration::from_millis(50));
+    assert_eq!(parsed.fill, Some(0.25));
+    assert_eq!(parsed.seed, Some(7));
+    assert_eq!(parsed.pattern, Some(PathBuf::from("gun.rle")));
+    assert_eq!(parsed.history_budget, 8 << 20);
+    assert_eq!(parsed.soup(), Some(Soup::new(7).with_density(0.25)));
+
+    let soup = options(&["--symmetry", "d4", "--soup-size", "16x12", "--seed", "1"]);
+    assert_eq!(soup.symmetry, Some(Symmetry::D4));
+    assert_eq!(soup.soup_size(), (16, 12));
+    assert_eq!(soup.soup(), Some(Soup::new(1).with_symmetry(Symmetry::D4)));
+    assert_eq!(options(&[]).soup(), None);
+
+    assert!(matches!(options(&["--topology", "unbounded"]).space, Space::Unbounded));
+
+    let batch = options(&["--headless", "--generations=50", "--until-stable", "--output", "out.cells", "--format", "life106"]);
+    assert!(batch.headless && batch.until_stable);
+    assert_eq!(batch.generations, Some(50));
+    assert_eq!(batch.output, Some(PathBuf::from("out.cells")));
+    assert_eq!(batch.format, Some(Format::Life106));
+
+    let search = options(&["--search", "500", "--threads", "4", "--generations", "2000", "--output", "report.txt"]);
+    assert_eq!((search.search, search.threads, search.generations), (Some(500), Some(4), Some(2000)));
+
+    let stepping = options(&["--threads", "3", "--no-region-tracking"]);
+    assert_eq!((stepping.threads, stepping.region_tracking), (Some(3), false));
+    assert!(options(&[]).region_tracking);
+
+}
+
+#[test]
+fn test_cli_parse_errors() {
+
+    assert!(matches!(run(&["--colour", "red"]), Err(CliError::UnknownFlag(_))));
+    assert!(matches!(run(&["--width"]), Err(CliError::MissingValue(_))));
+    assert!(matches!(run(&["--width", "0"]), Err(CliError::InvalidValue { .. })));
+    assert!(matches!(run(&["--headless", "--width", "18446744073709551615", "--height", "2"]),
+        Err(CliError::InvalidValue { .. })));
+    assert!(matches!(run(&["--width", "16384", "--height", "16385"]), Err(CliError::InvalidValue { .. })));
+    assert!(matches!(run(&["--width", "16384", "--height", "16384"]), Ok(Command::Run(_))));
+    assert!(matches!(run(&["--offset", "3"]), Err(CliError::InvalidValue { .. })));
+    assert!(matches!(run(&["--topology", "sphere"]), Err(CliError::InvalidValue { .. })));
+    assert!(matches!(run(&["--fill", "1.5"]), Err(CliError::InvalidValue { .. })));
+    assert!(matches!(run(&["--tick-rate", "fast"]), Err(CliError::InvalidValue { .. })));
+    assert!(matches!(run(&["--tick-rate", "10000"]), Err(CliError::InvalidValue { .. })));
+    assert!(matches!(run(&["--symmetry", "C3"]), Err(CliError::InvalidValue { .. })));
+    assert!(matches!(run(&["--soup-size", "16"]), Err(CliError::InvalidValue { .. })));
+    assert!(matches!(run(&["--rule", "B9/S23"]), Err(CliError::InvalidRule(_))));
+    assert!(matches!(run(&["--headless", "--format", "png"]), Err(CliError::InvalidValue { .. })));
+    assert!(matches!(run(&["--until-stable"]), Err(CliError::RequiresHeadless(_))));
+    assert!(matches!(run(&["--search", "10", "--until-stable"]), Err(CliError::RequiresHeadless(_))));
+    assert!(matches!(run(&["--threads", "0"]), Err(CliError::InvalidValue { .. })));
+    assert!(matches!(run(&["--search", "0"]), Err(CliError::InvalidValue { .. })));
+    assert!(matches!(run(&["--engine", "quadtree"]), Err(CliError::InvalidValue { .. })));
+    assert!(matches!(run(&["--engine", "hashlife"]), Err(CliError::HashLifeRequiresUnbounded)));
+
+}
+
+#[test]
+fn test_cli_build() {
+
+    // the demo glider gun
+    let game = options(&[]).build().unwrap();
+    assert_eq!(game.population(), 36);
+
+    // random fills are reproducible and the rule comes from the options
+    let fill = ["--width", "40", "--height", "30", "--fill", "0.5", "--seed", "3", "--rule", "B36/S23"];
+    let first = options(&fill).build().unwrap();
+    let second = options(&fill).build().unwrap();
+    assert_eq!(first.population(), second.population());
+    assert!(first.population() > 450 && first.population() < 750);
+    assert_eq!(first.get_rule(), &"B36/S23".parse::<rule::Rule>().unwrap());
+
+    // a symmetric soup in the middle of the grid
+    let soup = options(&["--width", "40", "--height", "30", "--soup-size", "10x10", "--symmetry", "C2", "--seed", "3"]);
+    let game = soup.build().unwrap();
+    let region = game.bounding_box().unwrap();
+    assert!(region.min.0 >= 15 && region.max.0 < 25 && region.min.1 >= 10 && region.max.1 < 20);
+    assert_eq!(game.population(), soup.soup().unwrap().cells((10, 10)).len());
+    assert!(matches!(options(&["--width", "40", "--height", "30", "--soup-size", "50x10"]).build(),
+        Err(CliError::Pattern(PatternError::DoesNotFit))));
+
+    // bundled patterns are found by name when there is no such file
+    let game = options(&["--pattern", "acorn", "--topology", "unbounded"]).build().unwrap();
+    assert_eq!(game.population(), 7);
+    assert!(matches!(options(&["--pattern", "no-such-pattern"]).build(), Err(CliError::Pattern(_))));
+
+    // threads and region tracking change how the grid is stepped, not what it turns into
+    let mut plain = options(&["--fill", "0.4", "--seed", "5", "--no-region-tracking"]).build().unwrap();
+    let mut threaded = options(&["--fill", "0.4", "--seed", "5", "--threads", "4"]).build().unwrap();
+    plain.advance(40).unwrap();
+    threaded.advance(40).unwrap();
+    assert_eq!(threaded.cells_in(&threaded.limits().unwrap()), plain.cells_in(&plain.limits().unwrap()));
+
+    // HashLife runs the same pattern, and jumps ahead just as well
+    let mut cells = options(&["--pattern", "acorn", "--topology", "unbounded"]).build().unwrap();
+    let mut hashlife = options(&["--pattern", "acorn", "--topology", "unbounded", "--engine", "hashlife"]).build().unwrap();
+    cells.advance(300).unwrap();
+    hashlife.advance(300).unwrap();
+    assert_eq!(hashlife.population(), cells.population());
+    assert_eq!(hashlife.bounding_box(), cells.bounding_box());
+    assert!(matches!(options(&["--topology", "unbounded", "--engine", "hashlife", "--rule", "B03/S23"]).build(),
+        Err(CliError::UnboundedBirthOnZero)));
+
+    // an offset so large it would overflow does not fit either
+    for topology in ["torus", "unbounded"].iter() {
+        assert!(matches!(options(&["--topology", topology, "--offset", "18446744073709551615,0"]).build(),
+            Err(CliError::Pattern(PatternError::DoesNotFit))));
+    }
+    let far = options(&["--topology", "unbounded", "--engine", "hashlife", "--offset", "4611686018427387904,0"]);
+    assert!(matches!(far.build(), Err(CliError::Pattern(PatternError::DoesNotFit))));
+
+    // the gun does not fit on a small grid, B0 can not be unbounded
+    assert!(matches!(options(&["--width", "30"]).build(), Err(CliError::Pattern(PatternError::DoesNotFit))));
+    assert!(matches!(options(&["--topology", "unbounded", "--rule", "B03/S23"]).build(),
+        Err(CliError::UnboundedBirthOnZero)));
+
+}
@@ -0,0 +1,231 @@
+
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+
+use crate::game::Simulation;
+
+// Most generation hashes kept while looking for a repeat. When full they are forgotten and the
+// search starts over, so only cycles entered after that are found
+pub const DEFAULT_CAPACITY: usize = 1 << 20;
+
+// A universe that repeats itself, from the first generation of the cycle
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Cycle {
+    pub start: u64,
+    pub period: u64
+}
+
+impl Cycle {
+
+    // A period of one means nothing changes anymore
+    pub fn is_still(&self) -> bool {
+        self.period == 1
+    }
+
+}
+
+impl fmt::Display for Cycle {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+
+        if self.is_still() {
+            write!(f, "still from generation {}", self.start)
+        } else {
+            write!(f, "period {} from generation {}", self.period, self.start)
+        }
+
+    }
+}
+
+// Finds when a simulation repeats by hashing every generation it is shown. Generations have to
+// be shown one after the other, anything else such as a rewind or a jump starts the search over.
+// Showing the same generation again does nothing, so edits have to reset the detector
+#[derive(Debug, Clone)]
+pub struct CycleDetector {
+    seen: HashMap<u64, u64>,
+    last: Option<u64>,
+    cycle: Option<Cycle>,
+    capacity: usize
+}
+
+impl Default for CycleDetector {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl CycleDetector {
+
+    pub fn new() -> Self {
+        Self::with_capacity(DEFAULT_CAPACITY)
+    }
+
+    pub fn with_capacity(capacity: usize) -> Self {
+
+        Self {
+            seen: HashMap::new(),
+            last: None,
+            cycle: None,
+            capacity: capacity.max(1)
+        }
+
+    }
+
+    pub fn cycle(&self) -> Option<Cycle> {
+        self.cycle
+    }
+
+    // Forgets everything, needed when cells are edited by hand
+    pub fn reset(&mut self) {
+        self.seen.clear();
+        self.last = None;
+        self.cycle = None;
+    }
+
+    // Looks at the current generation, returning the cycle once one has been found. Once the
+    // universe cycles it keeps doing so, so later generations may be skipped
+    pub fn observe(&mut self, game: &dyn Simulation) -> Option<Cycle> {
+
+        let generation = game.get_evolution();
+
+        if let Some(last) = self.last {
+            if generation == last {
+                return self.cycle;
+            }
+
+            let follows = generation == last + 1 || (self.cycle.is_some() && generation > last);
+            if !follows {
+                self.reset();
+            }
+        }
+
+        self.last = Some(generation);
+        if self.cycle.is_some() {
+            return self.cycle;
+        }
+
+        let hash = state_hash(game);
+        match self.seen.get(&hash) {
+            Some(&start) => self.cycle = Some(Cycle { start, period: generation - start }),
+            None => {
+                if self.seen.len() >= self.capacity {
+                    self.seen.clear();
+                }
+                self.seen.insert(hash, generation);
+            }
+        }
+
+        self.cycle
+
+    }
+
+}
+
+// Hash of the living cells. Two different generations sharing a hash is possible but unlikely
+// enough to be ignored
+pub fn state_hash(game: &dyn Simulation) -> u64 {
+
+    let mut cells = match game.bounding_box() {
+        Some(region) => game.cells_in(&region),
+        None => Vec::new()
+    };
+    cells.sort_unstable();
+
+    let mut hasher = DefaultHasher::new();
+    cells.hash(&mut hasher);
+    hasher.finish()
+
+}
+
+
+// tests
+#[cfg(test)]
+use crate::{cell, game, grid, rule, universe};
+
+#[cfg(test)]
+fn detect(game: &mut dyn Simulation, limit: u64) -> Option<Cycle> {
+
+    let mut detector = CycleDetector::new();
+    for _ in 0..limit {
+        if let Some(cycle) = detector.observe(game) {
+            return Some(cycle);
+        }
+        game.update().unwrap();
+    }
+
+    None
+
+}
+
+#[cfg(test)]
+fn unbounded(cells: &[(i64, i64)]) -> game::UnboundedLife {
+
+    let mut game = game::UnboundedLife::new(universe::Universe::new(), rule::Rule::default());
+    for position in cells.iter() {
+        game.set_state(position, cell::CellState::Alive).unwrap();
+    }
+
+    game
+
+}
+
+#[test]
+fn test_cycle_detection() {
+
+    // a blinker repeats every two generations from the start
+    let mut blinker = unbounded(&[(0, 0), (1, 0), (2, 0)]);
+    assert_eq!(detect(&mut blinker, 10), Some(Cycle { start: 0, period: 2 }));
+
+    // three cells of a block fill in after one generation
+    let mut block = unbounded(&[(0, 0), (1, 0), (0, 1)]);
+    let cycle = detect(&mut block, 10).unwrap();
+    assert_eq!(cycle, Cycle { start: 1, period: 1 });
+    assert!(cycle.is_still());
+    assert_eq!(cycle.to_string(), "still from generation 1");
+
+    // a glider on a torus comes back to the same place after travelling all the way around
+    let mut cell_grid = grid::Grid::new((8, 8));
+    for position in [(1, 0), (2, 1), (0, 2), (1, 2), (2, 2)].iter() {
+        cell_grid.set_state(position, cell::CellState::Alive).unwrap();
+    }
+    let mut glider = game::GameOfLife::new(cell_grid, rule::Rule::default());
+    assert_eq!(detect(&mut glider, 100), Some(Cycle { start: 0, period: 32 }));
+
+    // on an unbounded universe it never repeats
+    let mut glider = unbounded(&[(1, 0), (2, 1), (0, 2), (1, 2), (2, 2)]);
+    assert_eq!(detect(&mut glider, 100), None);
+
+}
+
+#[test]
+fn test_cycle_detector_resets() {
+
+    let mut blinker = unbounded(&[(0, 0), (1, 0), (2, 0)]);
+    let mut detector = CycleDetector::new();
+
+    detector.observe(&blinker);
+    blinker.update().unwrap();
+    detector.observe(&blinker);
+    blinker.update().unwrap();
+    assert_eq!(detector.observe(&blinker).map(|cycle| cycle.period), Some(2));
+
+    // skipping ahead keeps a cycle that was found
+    blinker.advance(5).unwrap();
+    assert!(detector.observe(&blinker).is_some());
+
+    // going back starts over
+    blinker.set_evolution(3);
+    assert_eq!(detector.observe(&blinker), None);
+    blinker.update().unwrap();
+    blinker.update().unwrap();
+    assert_eq!(detector.observe(&blinker), None);
+
+    // a full table is cleared, a cycle longer than it is never found
+    let mut detector = CycleDetector::with_capacity(1);
+    for _ in 0..6 {
+        assert_eq!(detector.observe(&blinker), None);
+        blinker.update().unwrap();
+    }
+
+}
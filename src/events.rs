@@ -3,26 +3,42 @@
 use std::io;
 use std::sync::mpsc;
 use std::sync::{
-    atomic::{AtomicBool, Ordering},
+    atomic::{AtomicBool, AtomicU64, Ordering},
     Arc,
 };
 use std::thread;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
-use termion::event::Key;
+use termion::event::{self, Key, MouseEvent};
 use termion::input::TermRead;
 
+// Longest the tick thread sleeps before looking at the tick rate again, so a new rate applies
+// quickly even after a slow one
+const MAX_TICK_SLEEP: Duration = Duration::from_millis(50);
+
+// How often the tick thread checks whether the last tick was handled, when the next one is due
+const PENDING_TICK_POLL: Duration = Duration::from_millis(1);
+
 pub enum Event<I> {
     Input(I),
+    Mouse(MouseEvent),
     Tick,
 }
 
-/// A small event handler that wrap termion input and tick events. Each event
-/// type is handled in its own thread and returned to a common `Receiver`
+/// A small event handler that wrap termion input, mouse and tick events. Each event
+/// type is handled in its own thread and returned to a common `Receiver`.
+///
+/// The tick rate can be changed while running. A zero tick rate ticks as fast as the ticks are
+/// handled: a new tick is only sent once the previous one was received, so ticks never pile up
 pub struct Events {
     rx: mpsc::Receiver<Event<Key>>,
+    // the handles only keep the threads owned, nothing joins them
+    #[allow(dead_code)]
     input_handle: thread::JoinHandle<()>,
     ignore_exit_key: Arc<AtomicBool>,
+    tick_rate: Arc<AtomicU64>,
+    tick_pending: Arc<AtomicBool>,
+    #[allow(dead_code)]
     tick_handle: thread::JoinHandle<()>,
 }
 
@@ -41,6 +57,18 @@ impl Default for Config {
     }
 }
 
+impl Default for Events {
+    fn default() -> Events {
+        Events::new()
+    }
+}
+
+// Whether the input thread stops after this event, which only the exit key does while it is
+// not ignored
+fn ends_input(config: &Config, ignore_exit_key: &AtomicBool, input: &event::Event) -> bool {
+    !ignore_exit_key.load(Ordering::Relaxed) && *input == event::Event::Key(config.exit_key)
+}
+
 impl Events {
     pub fn new() -> Events {
         Events::with_config(Config::default())
@@ -54,37 +82,71 @@ impl Events {
             let ignore_exit_key = ignore_exit_key.clone();
             thread::spawn(move || {
                 let stdin = io::stdin();
-                for evt in stdin.keys() {
-                    if let Ok(key) = evt {
-                        if let Err(err) = tx.send(Event::Input(key)) {
-                            eprintln!("{}", err);
-                            return;
-                        }
-                        if !ignore_exit_key.load(Ordering::Relaxed) && key == config.exit_key {
-                            return;
-                        }
+                for input in stdin.events().flatten() {
+                    let event = match &input {
+                        event::Event::Key(key) => Event::Input(*key),
+                        event::Event::Mouse(mouse) => Event::Mouse(*mouse),
+                        event::Event::Unsupported(_) => continue,
+                    };
+                    if let Err(err) = tx.send(event) {
+                        eprintln!("{}", err);
+                        return;
+                    }
+                    if ends_input(&config, &ignore_exit_key, &input) {
+                        return;
                     }
                 }
             })
         };
+        let tick_rate = Arc::new(AtomicU64::new(config.tick_rate.as_micros() as u64));
+        let tick_pending = Arc::new(AtomicBool::new(false));
         let tick_handle = {
-            thread::spawn(move || loop {
-                if tx.send(Event::Tick).is_err() {
-                    break;
+            let tick_rate = tick_rate.clone();
+            let tick_pending = tick_pending.clone();
+            thread::spawn(move || {
+                let mut last_tick = Instant::now();
+                loop {
+                    let rate = Duration::from_micros(tick_rate.load(Ordering::Relaxed));
+                    let elapsed = last_tick.elapsed();
+                    if elapsed < rate {
+                        thread::sleep((rate - elapsed).min(MAX_TICK_SLEEP));
+                        continue;
+                    }
+                    if tick_pending.swap(true, Ordering::Relaxed) {
+                        thread::sleep(PENDING_TICK_POLL);
+                        continue;
+                    }
+                    if tx.send(Event::Tick).is_err() {
+                        break;
+                    }
+                    last_tick = Instant::now();
                 }
-                thread::sleep(config.tick_rate);
             })
         };
         Events {
             rx,
             ignore_exit_key,
             input_handle,
+            tick_rate,
+            tick_pending,
             tick_handle,
         }
     }
 
     pub fn next(&self) -> Result<Event<Key>, mpsc::RecvError> {
-        self.rx.recv()
+        let event = self.rx.recv()?;
+        if let Event::Tick = event {
+            self.tick_pending.store(false, Ordering::Relaxed);
+        }
+        Ok(event)
+    }
+
+    pub fn tick_rate(&self) -> Duration {
+        Duration::from_micros(self.tick_rate.load(Ordering::Relaxed))
+    }
+
+    pub fn set_tick_rate(&self, tick_rate: Duration) {
+        self.tick_rate.store(tick_rate.as_micros() as u64, Ordering::Relaxed);
     }
 
     pub fn disable_exit_key(&mut self) {
@@ -95,3 +157,21 @@ impl Events {
         self.ignore_exit_key.store(false, Ordering::Relaxed);
     }
 }
+
+
+// tests
+#[test]
+fn test_events_exit_key() {
+
+    let config = Config::default();
+    let ignore_exit_key = AtomicBool::new(false);
+    let quit = event::Event::Key(Key::Char('q'));
+
+    assert!(ends_input(&config, &ignore_exit_key, &quit));
+    assert!(!ends_input(&config, &ignore_exit_key, &event::Event::Key(Key::Char('p'))));
+
+    // once the app decides when to quit, the exit key is passed on like any other
+    ignore_exit_key.store(true, Ordering::Relaxed);
+    assert!(!ends_input(&config, &ignore_exit_key, &quit));
+
+}
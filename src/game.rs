@@ -1,17 +1,99 @@
 
 use std::collections::HashMap;
+use std::thread;
 
 use crate::grid;
 use crate::cell;
+use crate::history;
+use crate::rule;
+use crate::universe;
+use tui::widgets::canvas::{Painter, Shape};
+
+// Cells born and cells that died in the most recent generation
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Changes {
+    pub births: usize,
+    pub deaths: usize
+}
+
+// Stepping interface shared by the simulation engines so the app can drive any of them
+pub trait Simulation: Shape {
+
+    fn update(&mut self) -> Result<(), grid::OutOfBoundsError>;
+
+    // Moves forward several generations at once, engines with a faster way to skip ahead override it
+    fn advance(&mut self, generations: u64) -> Result<(), grid::OutOfBoundsError> {
+
+        for _ in 0..generations {
+            self.update()?;
+        }
+
+        Ok(())
+
+    }
+
+    // Whether advance is faster than stepping one generation at a time, so callers should not
+    // look at every generation on the way
+    fn skips_ahead(&self) -> bool {
+        false
+    }
+
+    fn get_evolution(&self) -> u64;
+
+    // Moves the generation counter, used when cells are put back the way they were earlier
+    fn set_evolution(&mut self, evolution: u64);
+
+    fn get_rule(&self) -> &rule::Rule;
+
+    fn population(&self) -> usize;
+
+    fn bounding_box(&self) -> Option<universe::BoundingBox>;
 
+    // Births and deaths of the last generation, None before the first one or when the engine does
+    // not keep track of them
+    fn last_changes(&self) -> Option<Changes>;
+
+    // Population of the generations run so far
+    fn history(&self) -> &history::PopulationHistory;
+
+    // Name of the topology the cells live on
+    fn topology_name(&self) -> &'static str;
+
+    // Region cells can live in, None for engines without edges
+    fn limits(&self) -> Option<universe::BoundingBox>;
+
+    fn is_alive(&self, position: &(i64, i64)) -> bool;
+
+    fn set_state(&mut self, position: &(i64, i64), state: cell::CellState) -> Result<(), grid::OutOfBoundsError>;
+
+    // Living cells inside a window, used to draw part of a pattern
+    fn cells_in(&self, window: &universe::BoundingBox) -> Vec<(i64, i64)>;
+
+}
+
+// tui-rs integration, lets the canvas draw whichever engine is running
+impl Shape for Box<dyn Simulation> {
+    fn draw(&self, painter: &mut Painter) {
+        self.as_ref().draw(painter);
+    }
+}
+
+// Finite grid engine
 pub struct GameOfLife {
-    evolution: u32,
+    evolution: u64,
+    rule: rule::Rule,
+    threads: usize,
+    region_tracking: bool,
+    active_tiles: Option<Vec<bool>>,
+    last_grid: Option<grid::Grid>,
+    last_changes: Option<Changes>,
+    history: history::PopulationHistory,
     pub cell_grid: grid::Grid
 }
 
-impl GameOfLife {
+impl Default for GameOfLife {
 
-    pub fn default() -> Self {
+    fn default() -> Self {
 
         let grid_x: usize = 100;
         let grid_y: usize = 200;
@@ -19,13 +101,6 @@ impl GameOfLife {
 
         let mut seed: HashMap<(usize,usize), cell::CellState> = HashMap::new();
 
-        // glider
-        // seed.entry((3,3)).or_insert(cell::CellState::Alive);
-        // seed.entry((3,2)).or_insert(cell::CellState::Alive);
-        // seed.entry((3,1)).or_insert(cell::CellState::Alive);
-        // seed.entry((2,3)).or_insert(cell::CellState::Alive);
-        // seed.entry((1,2)).or_insert(cell::CellState::Alive);
-
         // Gosper glider gun
         seed.entry((20,27)).or_insert(cell::CellState::Alive);
         seed.entry((20,28)).or_insert(cell::CellState::Alive);
@@ -72,112 +147,220 @@ impl GameOfLife {
 
         Self {
             evolution: 0,
+            rule: rule::Rule::default(),
+            threads: 1,
+            region_tracking: true,
+            active_tiles: None,
+            last_grid: None,
+            last_changes: None,
+            history: history::PopulationHistory::new(),
             cell_grid: default_grid,
         }
 
     }
 
+}
+
+impl GameOfLife {
+
+    pub fn new(cell_grid: grid::Grid, rule: rule::Rule) -> Self {
+
+        Self {
+            evolution: 0,
+            rule,
+            threads: 1,
+            region_tracking: true,
+            active_tiles: None,
+            last_grid: None,
+            last_changes: None,
+            history: history::PopulationHistory::new(),
+            cell_grid
+        }
+
+    }
+
+    // Steps the grid as horizontal bands spread over this many threads
+    pub fn with_threads(mut self, threads: usize) -> Self {
+        self.threads = threads.max(1);
+        self
+    }
+
+    pub fn get_threads(&self) -> usize {
+        self.threads
+    }
+
+    // Only re-evaluates tiles that changed in the previous generation and their neighbors,
+    // which is on by default
+    pub fn with_region_tracking(mut self, region_tracking: bool) -> Self {
+        self.region_tracking = region_tracking;
+        self.active_tiles = None;
+        self
+    }
+
     pub fn update(&mut self) -> Result<(), grid::OutOfBoundsError> {
 
+        // recorded again in case cells were edited since the last generation
+        self.history.record(self.evolution, self.cell_grid.population());
         self.evolution += 1;
 
-        let mut delta: HashMap<(usize, usize), cell::CellState> = HashMap::new();
-
-        // Traverse the grid
-        for i in 0..self.cell_grid.get_size().0 {
-            for j in 0..self.cell_grid.get_size().1 {
+        // The next generation is written into a blank copy of the grid so every cell sees the
+        // current generation's neighbors
+        let mut next_grid = self.cell_grid.clone();
+        next_grid.clear();
 
-                // Compute neighbors at (i, j)
-                let neighbors = self.cell_grid.get_neighbors(&(i,j));
-                let neighbors = match neighbors {
+        // Edits made to the grid since the last update invalidate the tracked regions
+        let active_tiles = match (self.active_tiles.take(), &self.last_grid) {
+            (Some(tiles), Some(last_grid)) if *last_grid == self.cell_grid => Some(tiles),
+            _ => None
+        };
+        let active_tiles = active_tiles.as_deref();
 
-                    Ok(positions) => positions,
-                    Err(error) => return Err(error)
+        let mut bands = next_grid.bands_mut(self.threads);
 
-                };
+        if bands.len() == 1 {
 
-                // Count number of living neighbors
-                let mut alive = 0;
-                for indices in neighbors {
+            self.step_band(&mut bands[0], active_tiles)?;
 
-                    if let Some(neighbor_cell) = self.cell_grid.get_cell(&indices) {
+        } else {
 
-                        match neighbor_cell.get_state() {
-                            cell::CellState::Alive => {
-                                alive += 1;
-                            },
-                            cell::CellState::Dead => continue
-                        }
+            // every band only reads the current grid and writes its own rows of the next one, so
+            // the result is the same no matter how the rows are split up
+            let game = &*self;
+            let results: Vec<Result<(), grid::OutOfBoundsError>> = thread::scope(|scope| {
 
-                    } else {
-                        continue
-                    }
+                let handles: Vec<_> = bands.iter_mut()
+                    .map(|band| scope.spawn(move || game.step_band(band, active_tiles)))
+                    .collect();
 
-                }
+                handles.into_iter()
+                    .map(|handle| handle.join().unwrap_or(Err(grid::OutOfBoundsError)))
+                    .collect()
 
-                // Apply Game of Life rules and track changes to be made to the grid
-                self.play(alive, &(i,j), &mut delta);
+            });
 
+            for result in results {
+                result?;
             }
+
+        }
+
+        if self.region_tracking {
+            let changed = self.cell_grid.changed_tiles(&next_grid);
+            self.active_tiles = Some(self.spread_tiles(&changed));
+            self.last_grid = Some(next_grid.clone());
         }
 
-        // Update grid with changes
-        self.cell_grid.update(delta);
+        let (births, deaths) = self.cell_grid.births_and_deaths(&next_grid);
+        self.last_changes = Some(Changes { births, deaths });
+
+        self.cell_grid = next_grid;
+        self.history.record(self.evolution, self.cell_grid.population());
         Ok(())
 
     }
 
-    pub fn get_evolution(&self) -> u32 {
+    pub fn get_evolution(&self) -> u64 {
         self.evolution
     }
 
-    fn play(&self, living_neighbors: i32, current_position: &(usize, usize),  delta: &mut
-        HashMap<(usize, usize), cell::CellState>) {
+    pub fn get_rule(&self) -> &rule::Rule {
+        &self.rule
+    }
+
+    pub fn set_rule(&mut self, rule: rule::Rule) {
+        self.rule = rule;
+        self.active_tiles = None;
+    }
 
-        // GAME OF LIFE
-        if living_neighbors < 2 {
+    fn step_band(&self, band: &mut grid::GridBand, active_tiles: Option<&[bool]>) -> Result<(), grid::OutOfBoundsError> {
 
-            if let Some(cell) = self.cell_grid.get_cell(current_position) {
+        let width = self.cell_grid.get_size().0;
+        let (tile_columns, _) = self.cell_grid.tile_count();
 
-                match cell.get_state() {
-                    cell::CellState::Alive => {
-                        delta.entry(*current_position).or_insert(cell::CellState::Dead);
-                    },
-                    cell::CellState::Dead => ()
-                }
+        // Traverse the band row by row, matching the storage order
+        for j in band.rows() {
+            for tile_column in 0..tile_columns {
 
-            }
+                // Nothing around an inactive tile changed last generation, so neither will it
+                let tile = (j / grid::TILE_SIZE.1) * tile_columns + tile_column;
+                if let Some(false) = active_tiles.map(|active| active[tile]) {
+                    band.copy_tile_row(&self.cell_grid, tile_column, j);
+                    continue;
+                }
 
-        } else if living_neighbors == 2 || living_neighbors == 3 {
+                let columns = (tile_column * grid::TILE_SIZE.0)..((tile_column + 1) * grid::TILE_SIZE.0).min(width);
+                for i in columns {
 
-            if let Some(cell) = self.cell_grid.get_cell(current_position) {
+                    // Count number of living neighbors at (i, j)
+                    let alive = self.cell_grid.count_living_neighbors(&(i,j))?;
 
-                match cell.get_state() {
-                    cell::CellState::Alive => (),
-                    cell::CellState::Dead => {
-                        if living_neighbors == 3 {
-                            delta.entry(*current_position).or_insert(cell::CellState::Alive);
-                        }
+                    // Apply Game of Life rules
+                    if let cell::CellState::Alive = self.play(alive, &(i,j)) {
+                        band.set_state(&(i,j), cell::CellState::Alive)?;
                     }
+
                 }
 
             }
+        }
 
-        } else if living_neighbors > 3 {
+        Ok(())
 
-            if let Some(cell) = self.cell_grid.get_cell(current_position) {
+    }
 
-                match cell.get_state() {
-                    cell::CellState::Alive => {
-                        delta.entry(*current_position).or_insert(cell::CellState::Dead);
-                    },
-                    cell::CellState::Dead => ()
+    // Tiles to evaluate next generation: every changed tile and the tiles around it
+    fn spread_tiles(&self, changed: &[bool]) -> Vec<bool> {
+
+        let (columns, rows) = self.cell_grid.tile_count();
+        let mut active = vec![false; changed.len()];
+        let mut touches_border = false;
+
+        for ty in 0..rows {
+            for tx in 0..columns {
+
+                if !changed[ty * columns + tx] {
+                    continue;
+                }
+
+                for ny in ty.saturating_sub(1)..=(ty + 1).min(rows - 1) {
+                    for nx in tx.saturating_sub(1)..=(tx + 1).min(columns - 1) {
+                        active[ny * columns + nx] = true;
+                    }
+                }
+
+                if tx == 0 || ty == 0 || tx + 1 == columns || ty + 1 == rows {
+                    touches_border = true;
                 }
 
             }
+        }
 
+        // Depending on the topology an edge cell can neighbor cells on any other edge
+        if touches_border {
+            for ty in 0..rows {
+                for tx in 0..columns {
+                    if tx == 0 || ty == 0 || tx + 1 == columns || ty + 1 == rows {
+                        active[ty * columns + tx] = true;
+                    }
+                }
+            }
         }
 
+        active
+
+    }
+
+    fn play(&self, living_neighbors: usize, current_position: &(usize, usize)) -> cell::CellState {
+
+        // Apply the birth/survival rule to the cell's current state
+        let state = if self.cell_grid.is_alive(current_position) {
+            cell::CellState::Alive
+        } else {
+            cell::CellState::Dead
+        };
+
+        self.rule.next_state(&state, living_neighbors)
+
     }
 
     pub fn display(&self) {
@@ -189,3 +372,355 @@ impl GameOfLife {
 
 }
 
+impl Simulation for GameOfLife {
+
+    fn update(&mut self) -> Result<(), grid::OutOfBoundsError> {
+        GameOfLife::update(self)
+    }
+
+    fn get_evolution(&self) -> u64 {
+        self.evolution
+    }
+
+    fn set_evolution(&mut self, evolution: u64) {
+        self.evolution = evolution;
+        self.last_changes = None;
+    }
+
+    fn get_rule(&self) -> &rule::Rule {
+        &self.rule
+    }
+
+    fn population(&self) -> usize {
+        self.cell_grid.population()
+    }
+
+    fn bounding_box(&self) -> Option<universe::BoundingBox> {
+
+        let cells: Vec<(i64, i64)> = self.cell_grid.live_cells().iter()
+            .map(|(x, y)| (*x as i64, *y as i64))
+            .collect();
+
+        universe::BoundingBox::from_cells(cells.iter())
+
+    }
+
+    fn last_changes(&self) -> Option<Changes> {
+        self.last_changes
+    }
+
+    fn history(&self) -> &history::PopulationHistory {
+        &self.history
+    }
+
+    fn topology_name(&self) -> &'static str {
+        self.cell_grid.get_topology().name()
+    }
+
+    fn limits(&self) -> Option<universe::BoundingBox> {
+
+        let size = self.cell_grid.get_size();
+        Some(universe::BoundingBox { min: (0, 0), max: (size.0 as i64 - 1, size.1 as i64 - 1) })
+
+    }
+
+    fn is_alive(&self, position: &(i64, i64)) -> bool {
+
+        match grid_position(position) {
+            Some(position) => self.cell_grid.is_alive(&position),
+            None => false
+        }
+
+    }
+
+    fn set_state(&mut self, position: &(i64, i64), state: cell::CellState) -> Result<(), grid::OutOfBoundsError> {
+        let position = grid_position(position).ok_or(grid::OutOfBoundsError)?;
+        self.cell_grid.set_state(&position, state)
+    }
+
+    fn cells_in(&self, window: &universe::BoundingBox) -> Vec<(i64, i64)> {
+
+        self.cell_grid.live_cells().iter()
+            .map(|(x, y)| (*x as i64, *y as i64))
+            .filter(|position| window.contains(position))
+            .collect()
+
+    }
+
+}
+
+fn grid_position(position: &(i64, i64)) -> Option<(usize, usize)> {
+
+    if position.0 < 0 || position.1 < 0 {
+        return None;
+    }
+
+    Some((position.0 as usize, position.1 as usize))
+
+}
+
+impl Shape for GameOfLife {
+    fn draw(&self, painter: &mut Painter) {
+        self.cell_grid.draw(painter);
+    }
+}
+
+
+// Unbounded engine, the universe grows as patterns travel so nothing ever wraps around
+pub struct UnboundedLife {
+    evolution: u64,
+    rule: rule::Rule,
+    last_changes: Option<Changes>,
+    history: history::PopulationHistory,
+    pub universe: universe::Universe
+}
+
+impl Default for UnboundedLife {
+
+    // same seed as the default finite grid
+    fn default() -> Self {
+        let universe = universe::Universe::from(&GameOfLife::default().cell_grid);
+        Self::new(universe, rule::Rule::default())
+    }
+
+}
+
+impl UnboundedLife {
+
+    pub fn new(universe: universe::Universe, rule: rule::Rule) -> Self {
+
+        Self {
+            evolution: 0,
+            rule,
+            last_changes: None,
+            history: history::PopulationHistory::new(),
+            universe
+        }
+
+    }
+
+    pub fn set_rule(&mut self, rule: rule::Rule) {
+        self.rule = rule;
+    }
+
+}
+
+impl Simulation for UnboundedLife {
+
+    fn update(&mut self) -> Result<(), grid::OutOfBoundsError> {
+
+        self.history.record(self.evolution, self.universe.population());
+        self.evolution += 1;
+
+        let next = self.universe.step(&self.rule);
+        let (births, deaths) = self.universe.births_and_deaths(&next);
+        self.last_changes = Some(Changes { births, deaths });
+
+        self.universe = next;
+        self.history.record(self.evolution, self.universe.population());
+        Ok(())
+
+    }
+
+    fn get_evolution(&self) -> u64 {
+        self.evolution
+    }
+
+    fn set_evolution(&mut self, evolution: u64) {
+        self.evolution = evolution;
+        self.last_changes = None;
+    }
+
+    fn get_rule(&self) -> &rule::Rule {
+        &self.rule
+    }
+
+    fn population(&self) -> usize {
+        self.universe.population()
+    }
+
+    fn bounding_box(&self) -> Option<universe::BoundingBox> {
+        self.universe.bounding_box()
+    }
+
+    fn last_changes(&self) -> Option<Changes> {
+        self.last_changes
+    }
+
+    fn history(&self) -> &history::PopulationHistory {
+        &self.history
+    }
+
+    fn topology_name(&self) -> &'static str {
+        "unbounded"
+    }
+
+    fn limits(&self) -> Option<universe::BoundingBox> {
+        None
+    }
+
+    fn is_alive(&self, position: &(i64, i64)) -> bool {
+        self.universe.is_alive(position)
+    }
+
+    fn set_state(&mut self, position: &(i64, i64), state: cell::CellState) -> Result<(), grid::OutOfBoundsError> {
+        self.universe.set_state(position, state);
+        Ok(())
+    }
+
+    fn cells_in(&self, window: &universe::BoundingBox) -> Vec<(i64, i64)> {
+        self.universe.live_cells().into_iter().filter(|position| window.contains(position)).collect()
+    }
+
+}
+
+impl Shape for UnboundedLife {
+    fn draw(&self, painter: &mut Painter) {
+        self.universe.draw(painter);
+    }
+}
+
+
+// tests
+#[cfg(test)]
+
+#[test]
+fn test_unbounded_matches_grid_before_wrapping() {
+
+    let mut game = GameOfLife::default();
+    let mut unbounded = UnboundedLife::default();
+
+    // the gun's gliders need far more than 100 generations to reach the edge of the grid
+    for _ in 0..100 {
+        Simulation::update(&mut game).unwrap();
+        unbounded.update().unwrap();
+    }
+
+    assert_eq!(unbounded.get_evolution(), 100);
+    assert_eq!(unbounded.universe, universe::Universe::from(&game.cell_grid));
+    assert_eq!(unbounded.bounding_box(), game.bounding_box());
+
+}
+
+#[cfg(test)]
+fn striped_grid(size: (usize, usize)) -> grid::Grid {
+
+    let mut cell_grid = grid::Grid::new(size);
+    for j in 0..size.1 {
+        for i in 0..size.0 {
+            if (i * 7 + j * 13 + i * j) % 5 < 2 {
+                cell_grid.set_state(&(i, j), cell::CellState::Alive).unwrap();
+            }
+        }
+    }
+
+    cell_grid
+
+}
+
+#[test]
+fn test_threaded_update_matches_single_thread() {
+
+    let rule = rule::Rule::default();
+
+    for threads in [2, 3, 7, 64].iter() {
+
+        let mut single = GameOfLife::new(striped_grid((130, 45)), rule);
+        let mut threaded = GameOfLife::new(striped_grid((130, 45)), rule).with_threads(*threads);
+
+        for _ in 0..30 {
+            single.update().unwrap();
+            threaded.update().unwrap();
+            assert!(threaded.cell_grid == single.cell_grid);
+        }
+
+    }
+
+}
+
+#[test]
+fn test_region_tracking_matches_full_update() {
+
+    let rule = rule::Rule::default();
+    let topologies: Vec<std::sync::Arc<dyn crate::topology::Topology>> = vec![
+        std::sync::Arc::new(crate::topology::Torus), std::sync::Arc::new(crate::topology::KleinBottle)
+    ];
+
+    for topology in topologies {
+
+        let mut seed = grid::Grid::with_topology((150, 40), topology);
+        for (x, y) in [(1, 0), (2, 1), (0, 2), (1, 2), (2, 2)].iter() {
+            seed.set_state(&(x + 70, y + 5), cell::CellState::Alive).unwrap();
+        }
+
+        let mut tracked = GameOfLife::new(seed.clone(), rule).with_threads(3);
+        let mut full = GameOfLife::new(seed, rule).with_region_tracking(false);
+
+        for generation in 0..200 {
+
+            // edits between generations must be picked up even inside inactive tiles
+            if generation == 100 {
+                for game in [&mut tracked, &mut full].iter_mut() {
+                    game.cell_grid.set_state(&(10, 20), cell::CellState::Alive).unwrap();
+                    game.cell_grid.set_state(&(11, 20), cell::CellState::Alive).unwrap();
+                    game.cell_grid.set_state(&(12, 20), cell::CellState::Alive).unwrap();
+                }
+            }
+
+            tracked.update().unwrap();
+            full.update().unwrap();
+            assert!(tracked.cell_grid == full.cell_grid);
+
+        }
+
+    }
+
+}
+
+// cargo test --release bench_region_tracking -- --ignored --nocapture
+#[test]
+#[ignore]
+fn bench_region_tracking() {
+
+    for region_tracking in [false, true].iter() {
+
+        // the glider gun on a mostly empty board
+        let mut cell_grid = grid::Grid::new((2000, 2000));
+        for position in GameOfLife::default().cell_grid.live_cells() {
+            cell_grid.set_state(&position, cell::CellState::Alive).unwrap();
+        }
+
+        let mut game = GameOfLife::new(cell_grid, rule::Rule::default()).with_region_tracking(*region_tracking);
+
+        let start = std::time::Instant::now();
+        for _ in 0..100 {
+            game.update().unwrap();
+        }
+
+        eprintln!("region tracking {}: {:?} per generation", region_tracking, start.elapsed() / 100);
+
+    }
+
+}
+
+// cargo test --release bench_threaded_update -- --ignored --nocapture
+#[test]
+#[ignore]
+fn bench_threaded_update() {
+
+    let threads = thread::available_parallelism().map(|n| n.get()).unwrap_or(4).max(2);
+
+    for threads in [1, threads].iter() {
+
+        let mut game = GameOfLife::new(striped_grid((2000, 2000)), rule::Rule::default()).with_threads(*threads);
+
+        let start = std::time::Instant::now();
+        for _ in 0..20 {
+            game.update().unwrap();
+        }
+
+        eprintln!("{} thread(s): {:?} per generation", threads, start.elapsed() / 20);
+
+    }
+
+}
@@ -1,8 +1,12 @@
 
 use std::fmt;
 use std::collections::HashMap;
+use std::ops::Range;
+use std::sync::Arc;
 
 use crate::cell;
+use crate::random::Random;
+use crate::topology::{self, Topology};
 use tui::{
     style::Color,
     widgets::canvas::{Painter, Shape},
@@ -17,39 +21,54 @@ impl fmt::Display for OutOfBoundsError {
     }
 }
 
-#[derive(Debug)]
-pub enum GridDirection {
-    Up { x: usize, y: usize },
-    Down { x: usize, y: usize },
-    Left { x: usize, y: usize },
-    Right { x: usize, y: usize },
-    UpLeft { x: usize, y: usize },
-    UpRight { x: usize, y: usize },
-    DownLeft { x: usize, y: usize },
-    DownRight { x: usize, y: usize }
-}
+// Tiles used to track which parts of the grid change. A tile is one storage word wide, so each of
+// its rows can be compared or copied as a single word
+pub const TILE_SIZE: (usize, usize) = (64, 16);
 
-// TODO replace this
-#[derive(Debug)]
-pub enum PositionDescription {
-    Center,
-    TopBound,
-    BottomBound,
-    RightBound,
-    LeftBound,
-    TopLeftCorner,
-    TopRightCorner,
-    BottomLeftCorner,
-    BottomRightCorner
+// Rectangle of cells on a grid, both corners included
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Region {
+    pub min: (usize, usize),
+    pub max: (usize, usize)
 }
 
+impl Region {
+
+    // The rectangle between two opposite corners, given in any order
+    pub fn new(corner: (usize, usize), other: (usize, usize)) -> Self {
+
+        Self {
+            min: (corner.0.min(other.0), corner.1.min(other.1)),
+            max: (corner.0.max(other.0), corner.1.max(other.1))
+        }
+
+    }
+
+    pub fn size(&self) -> (usize, usize) {
+        (self.max.0 - self.min.0 + 1, self.max.1 - self.min.1 + 1)
+    }
+
+    pub fn contains(&self, position: &(usize, usize)) -> bool {
+        (self.min.0..=self.max.0).contains(&position.0) && (self.min.1..=self.max.1).contains(&position.1)
+    }
+
+    // positions of every cell, row by row
+    pub fn positions(&self) -> impl Iterator<Item = (usize, usize)> {
+        let (min, max) = (self.min, self.max);
+        (min.1..=max.1).flat_map(move |y| (min.0..=max.0).map(move |x| (x, y)))
+    }
+
+}
 
-// 2D grid finite grid
-#[derive(Clone)]
+// 2D grid finite grid, stored densely as one bit per cell in row-major order
+#[derive(Debug, Clone)]
 pub struct Grid {
     size: (usize, usize),
-    state: HashMap<(usize, usize), cell::Cell>,
+    words_per_row: usize,
+    state: Vec<u64>,
+    topology: Arc<dyn Topology>,
     color: Color,
+    #[allow(dead_code)]
     dim: usize
 }
 
@@ -57,22 +76,19 @@ impl Grid {
 
     // static method
     pub fn new(size: (usize, usize)) -> Self {
+        Self::with_topology(size, Arc::new(topology::Torus))
+    }
 
-        let mut state = HashMap::<(usize, usize), cell::Cell>::new();
-
-        for i in 0..size.0 {
-
-            for j in 0..size.1 {
-
-                let cell = cell::Cell::new(cell::CellState::Dead, (i,j));
-                state.entry( (i,j) ).or_insert(cell);
+    pub fn with_topology(size: (usize, usize), topology: Arc<dyn Topology>) -> Self {
 
-            }
-        }
+        // every row starts on a fresh word so rows can be addressed independently
+        let words_per_row = size.0.div_ceil(64);
 
         Self {
             size,
-            state,
+            words_per_row,
+            state: vec![0; words_per_row * size.1],
+            topology,
             color: Color::Cyan,
             dim: 2
         }
@@ -87,18 +103,10 @@ impl Grid {
 
             let mut x_chars = vec!['-'; self.size.0];
 
-            for i in 0..self.size.0 {
-
-                // safely unwrap optional retrieved from hashmap
-                if let Some(cell) = self.state.get(&(i,j)) {
-
-                    match cell.get_state() {
-                        cell::CellState::Alive => x_chars[i] = 'x',
-                        cell::CellState::Dead => continue
-                    }
-
+            for (i, x_char) in x_chars.iter_mut().enumerate() {
+                if self.is_alive(&(i,j)) {
+                    *x_char = 'x';
                 }
-
             }
 
             let row_string: String = x_chars.into_iter().collect();
@@ -110,10 +118,9 @@ impl Grid {
 
     pub fn update(&mut self, updated_cells: HashMap<(usize, usize), cell::CellState>) {
 
+        // positions outside the grid are ignored
         for (position, new_state) in updated_cells.iter() {
-            if let Some(cell) = self.state.get_mut(position) {
-                cell.state = *new_state;
-            }
+            let _ = self.set_state(position, *new_state);
         }
 
     }
@@ -122,267 +129,400 @@ impl Grid {
         &self.size
     }
 
-    pub fn get_cell(&self, position: &(usize, usize)) -> Option<&cell::Cell> {
-        self.state.get(position)
+    // Cell at a position, None off the grid. This used to borrow the Cell stored in the grid, but
+    // cells are single bits now, so it hands back a Cell made on the spot. Callers own the copy
+    // and do not see later changes to the grid
+    pub fn get_cell(&self, position: &(usize, usize)) -> Option<cell::Cell> {
+
+        if !self.contains(position) {
+            return None;
+        }
+
+        let state = if self.is_alive(position) {
+            cell::CellState::Alive
+        } else {
+            cell::CellState::Dead
+        };
+
+        Some(cell::Cell::new(state, *position))
+
+    }
+
+    pub fn contains(&self, position: &(usize, usize)) -> bool {
+        position.0 < self.size.0 && position.1 < self.size.1
     }
 
-    pub fn get_neighbors(&self, position: &(usize, usize)) -> Result<Vec<(usize, usize)>, OutOfBoundsError> {
+    // positions outside the grid are never alive
+    pub fn is_alive(&self, position: &(usize, usize)) -> bool {
+
+        if !self.contains(position) {
+            return false;
+        }
+
+        let (word, bit) = self.locate(position);
+        self.state[word] & (1 << bit) != 0
+
+    }
+
+    pub fn set_state(&mut self, position: &(usize, usize), state: cell::CellState) -> Result<(), OutOfBoundsError> {
+
+        if !self.contains(position) {
+            return Err(OutOfBoundsError);
+        }
+
+        let (word, bit) = self.locate(position);
+        match state {
+            cell::CellState::Alive => self.state[word] |= 1 << bit,
+            cell::CellState::Dead => self.state[word] &= !(1 << bit)
+        }
+
+        Ok(())
+
+    }
+
+    pub fn clear(&mut self) {
+        self.state.iter_mut().for_each(|word| *word = 0);
+    }
+
+    pub fn population(&self) -> usize {
+        self.state.iter().map(|word| word.count_ones() as usize).sum()
+    }
+
+    // Cells alive in the next grid but not in this one, and cells alive in this one but not in the
+    // next. Both grids must have the same size
+    pub fn births_and_deaths(&self, next: &Grid) -> (usize, usize) {
+
+        self.state.iter().zip(next.state.iter()).fold((0, 0), |(births, deaths), (word, next_word)| {
+            (births + (next_word & !word).count_ones() as usize, deaths + (word & !next_word).count_ones() as usize)
+        })
+
+    }
+
+    // positions of every living cell, row by row
+    pub fn live_cells(&self) -> Vec<(usize, usize)> {
+
+        let mut cells = Vec::new();
+
+        for (index, word) in self.state.iter().enumerate() {
+
+            let mut bits = *word;
+            while bits != 0 {
+
+                let bit = bits.trailing_zeros() as usize;
+                let y = index / self.words_per_row;
+                let x = (index % self.words_per_row) * 64 + bit;
+                cells.push((x, y));
+                bits &= bits - 1;
 
-        let mut neighbors: Vec<(usize, usize)> = Vec::new();
-
-        // apply the search policy
-        let search = self.search_policy(position);
-
-        for direction in search?.iter() {
-            match direction {
-                GridDirection::Up{x, y} => {
-                    neighbors.push((*x, *y)); // since search will go out of scope, dereference
-                },
-                GridDirection::Down{x, y} => {
-                    neighbors.push((*x, *y)); // since search will go out of scope, dereference
-                },
-                GridDirection::Left{x, y} => {
-                    neighbors.push((*x, *y)); // since search will go out of scope, dereference
-                },
-                GridDirection::Right{x, y} => {
-                    neighbors.push((*x, *y)); // since search will go out of scope, dereference
-                },
-                GridDirection::UpLeft{x, y} => {
-                    neighbors.push((*x, *y)); // since search will go out of scope, dereference
-                },
-                GridDirection::UpRight{x, y} => {
-                    neighbors.push((*x, *y)); // since search will go out of scope, dereference
-                },
-                GridDirection::DownLeft{x, y} => {
-                    neighbors.push((*x, *y)); // since search will go out of scope, dereference
-                },
-                GridDirection::DownRight{x, y} => {
-                    neighbors.push((*x, *y)); // since search will go out of scope, dereference
-                }
             }
+
         }
 
-        Ok(neighbors)
+        cells
+
+    }
+
+    // Region operations. They all fail without changing anything when a region does not lie
+    // wholly on the grid
+
+    fn check(&self, region: &Region) -> Result<(), OutOfBoundsError> {
+        if self.contains(&region.max) { Ok(()) } else { Err(OutOfBoundsError) }
+    }
+
+    // Grid the size of a region holding its cells, to be pasted elsewhere
+    pub fn copy_region(&self, region: &Region) -> Result<Grid, OutOfBoundsError> {
+
+        self.check(region)?;
+
+        let mut copy = Grid::with_topology(region.size(), self.topology.clone());
+        for (x, y) in region.positions() {
+            if self.is_alive(&(x, y)) {
+                copy.set_state(&(x - region.min.0, y - region.min.1), cell::CellState::Alive)?;
+            }
+        }
+
+        Ok(copy)
 
     }
 
-    pub fn get_position_description(&self, position: &(usize, usize)) -> Result<PositionDescription, OutOfBoundsError> {
+    pub fn cut_region(&mut self, region: &Region) -> Result<Grid, OutOfBoundsError> {
+        let copy = self.copy_region(region)?;
+        self.fill_region(region, cell::CellState::Dead)?;
+        Ok(copy)
+    }
 
-        let description: PositionDescription;
+    // Writes every cell of another grid, dead ones included, with its top left corner at the offset
+    pub fn paste(&mut self, source: &Grid, offset: (usize, usize)) -> Result<(), OutOfBoundsError> {
 
-        if position.0 == 0 && position.1 < (self.size.1-1) && position.1 > 0 {
+        if source.size.0 == 0 || source.size.1 == 0 {
+            return Ok(());
+        }
 
-            description = PositionDescription::LeftBound;
+        let region = Region::new(offset, (offset.0 + source.size.0 - 1, offset.1 + source.size.1 - 1));
+        self.fill_region(&region, cell::CellState::Dead)?;
+        for (x, y) in source.live_cells() {
+            self.set_state(&(offset.0 + x, offset.1 + y), cell::CellState::Alive)?;
+        }
 
-        } else if position.0 == (self.size.0-1) && position.1 < (self.size.1-1) && position.1 > 0 {
+        Ok(())
 
-            description = PositionDescription::RightBound;
+    }
 
-        } else if position.0 < (self.size.0-1) && position.0 > 0 && position.1 == 0 {
+    pub fn fill_region(&mut self, region: &Region, state: cell::CellState) -> Result<(), OutOfBoundsError> {
 
-            description = PositionDescription::BottomBound;
+        self.check(region)?;
+        for position in region.positions() {
+            self.set_state(&position, state)?;
+        }
 
-        } else if position.0 < (self.size.0-1) && position.0 > 0 && position.1 == (self.size.1-1) {
+        Ok(())
 
-            description = PositionDescription::TopBound;
+    }
 
-        } else if position.0 == 0 && position.1 == 0 {
+    // Every cell of the region is brought to life with the given probability, or killed
+    pub fn randomize_region(&mut self, region: &Region, density: f64, random: &mut Random) -> Result<(), OutOfBoundsError> {
 
-            description = PositionDescription::BottomLeftCorner;
+        self.check(region)?;
+        for position in region.positions() {
+            let state = if random.chance(density) { cell::CellState::Alive } else { cell::CellState::Dead };
+            self.set_state(&position, state)?;
+        }
 
-        } else if position.0 == 0 && position.1 == (self.size.1-1) {
+        Ok(())
 
-            description = PositionDescription::TopLeftCorner;
+    }
 
-        } else if position.0 == (self.size.0-1) && position.1 == (self.size.1-1) {
+    // Turns a region a quarter of the way clockwise around its top left corner, returning the
+    // region it covers afterwards. Cells it no longer covers are cleared
+    pub fn rotate_region(&mut self, region: &Region) -> Result<Region, OutOfBoundsError> {
 
-            description = PositionDescription::TopRightCorner;
+        let size = region.size();
+        let turned = Region::new(region.min, (region.min.0 + size.1 - 1, region.min.1 + size.0 - 1));
+        self.check(&turned)?;
 
-        } else if position.0 == (self.size.0-1) && position.1 == 0 {
+        let copy = self.cut_region(region)?;
+        self.paste(&copy.rotated(), turned.min)?;
+        Ok(turned)
 
-            description = PositionDescription::BottomRightCorner;
+    }
 
-        } else if position.0 > 0 && position.0 < (self.size.0-1) && position.1 > 0 && position.1 <
-            (self.size.1-1) {
+    pub fn flip_region_horizontally(&mut self, region: &Region) -> Result<(), OutOfBoundsError> {
+        let copy = self.copy_region(region)?;
+        self.paste(&copy.flipped_horizontally(), region.min)
+    }
 
-            description = PositionDescription::Center;
+    pub fn flip_region_vertically(&mut self, region: &Region) -> Result<(), OutOfBoundsError> {
+        let copy = self.copy_region(region)?;
+        self.paste(&copy.flipped_vertically(), region.min)
+    }
 
-        } else {
+    // The whole grid turned a quarter of the way clockwise, so its width and height swap
+    pub fn rotated(&self) -> Grid {
+        let height = self.size.1;
+        self.transformed((self.size.1, self.size.0), |(x, y)| (height - 1 - y, x))
+    }
+
+    // The whole grid mirrored left to right
+    pub fn flipped_horizontally(&self) -> Grid {
+        let width = self.size.0;
+        self.transformed(self.size, |(x, y)| (width - 1 - x, y))
+    }
+
+    // The whole grid turned upside down
+    pub fn flipped_vertically(&self) -> Grid {
+        let height = self.size.1;
+        self.transformed(self.size, |(x, y)| (x, height - 1 - y))
+    }
+
+    fn transformed<F>(&self, size: (usize, usize), transform: F) -> Grid
+        where F: Fn((usize, usize)) -> (usize, usize)
+    {
+
+        let mut result = Grid::with_topology(size, self.topology.clone());
+        for position in self.live_cells() {
+            let _ = result.set_state(&transform(position), cell::CellState::Alive);
+        }
+
+        result
+
+    }
+
+    fn locate(&self, position: &(usize, usize)) -> (usize, usize) {
+        (position.1 * self.words_per_row + position.0 / 64, position.0 % 64)
+    }
+
+    // number of tile columns and tile rows covering the grid
+    pub fn tile_count(&self) -> (usize, usize) {
+        (self.words_per_row, self.size.1.div_ceil(TILE_SIZE.1))
+    }
+
+    // Flags every tile, in row-major order, whose cells differ between the two grids
+    pub fn changed_tiles(&self, other: &Grid) -> Vec<bool> {
+
+        let (columns, rows) = self.tile_count();
+        let mut changed = vec![false; columns * rows];
+
+        if self.size != other.size {
+            return vec![true; columns * rows];
+        }
+
+        for (index, (word, other_word)) in self.state.iter().zip(other.state.iter()).enumerate() {
+            if word != other_word {
+                let (column, y) = (index % self.words_per_row, index / self.words_per_row);
+                changed[(y / TILE_SIZE.1) * columns + column] = true;
+            }
+        }
+
+        changed
+
+    }
+
+    // Splits the grid into at most `count` horizontal bands of whole rows that can be written
+    // independently, e.g. from separate threads
+    pub fn bands_mut(&mut self, count: usize) -> Vec<GridBand<'_>> {
+
+        let count = count.clamp(1, self.size.1.max(1));
+        let rows_per_band = self.size.1.div_ceil(count).max(1);
+
+        let width = self.size.0;
+        let words_per_row = self.words_per_row;
+
+        self.state.chunks_mut((rows_per_band * words_per_row).max(1))
+            .enumerate()
+            .map(|(index, state)| {
+                let first_row = index * rows_per_band;
+                let rows = first_row..(first_row + state.len() / words_per_row.max(1));
+                GridBand { rows, width, words_per_row, state }
+            })
+            .collect()
 
+    }
+
+    pub fn get_topology(&self) -> &dyn Topology {
+        self.topology.as_ref()
+    }
+
+    pub fn get_neighbors(&self, position: &(usize, usize)) -> Result<Vec<(usize, usize)>, OutOfBoundsError> {
+
+        if position.0 >= self.size.0 || position.1 >= self.size.1 {
             return Err(OutOfBoundsError);
+        }
+
+        let mut neighbors: Vec<(usize, usize)> = Vec::with_capacity(8);
+        let (x, y) = (position.0 as isize, position.1 as isize);
+
+        // the topology decides where (and whether) positions past the edges land on the grid
+        for dx in -1..=1 {
+            for dy in -1..=1 {
+
+                if dx == 0 && dy == 0 {
+                    continue;
+                }
+
+                if let Some(neighbor) = self.topology.resolve((x + dx, y + dy), self.size) {
+                    neighbors.push(neighbor);
+                }
+
+            }
+        }
+
+        Ok(neighbors)
+
+    }
+
+    pub fn count_living_neighbors(&self, position: &(usize, usize)) -> Result<usize, OutOfBoundsError> {
 
+        if !self.contains(position) {
+            return Err(OutOfBoundsError);
         }
 
-        Ok(description)
-
-    }
-
-
-    pub fn search_policy(&self, position: &(usize, usize)) -> Result<Vec<GridDirection>, OutOfBoundsError> {
-
-        let search: Vec<GridDirection>;
-        let mut dimensions = *self.get_size();
-        // dimensions copies the result from get_dimensions()
-        dimensions.0 -= 1;
-        dimensions.1 -= 1;
-
-        // ? evaluates the Result and expands the Ok()'s or automatically returns the Err()
-        // unwrap evaluates the Result match and automatically raises a panic! for Err()
-        match self.get_position_description(position)? {
-            PositionDescription::Center => {
-                search = vec![
-                    GridDirection::Up{ x: position.0, y: position.1 + 1 },
-                    GridDirection::Down{ x: position.0, y: position.1 - 1 },
-                    GridDirection::Left{ x: position.0 - 1, y: position.1 },
-                    GridDirection::Right{ x: position.0 + 1, y: position.1 },
-                    GridDirection::UpLeft{ x: position.0 - 1, y: position.1 + 1 },
-                    GridDirection::UpRight{ x: position.0 + 1, y: position.1 + 1 },
-                    GridDirection::DownLeft{ x: position.0 - 1, y: position.1 - 1 },
-                    GridDirection::DownRight{ x: position.0 + 1, y: position.1 -1 }
-                ];
-            },
-            PositionDescription::TopBound => {
-                search = vec![
-                    GridDirection::Down{ x: position.0, y: position.1 - 1 },
-                    GridDirection::Left{ x: position.0 - 1, y: position.1 },
-                    GridDirection::Right{ x: position.0 + 1, y: position.1 },
-                    GridDirection::DownLeft{ x: position.0 - 1, y: position.1 - 1 },
-                    GridDirection::DownRight{ x: position.0 + 1, y: position.1 - 1 },
-
-                    // wrap around
-                    GridDirection::Up{ x: position.0, y: 0 },
-                    GridDirection::UpLeft{ x: position.0 - 1, y: 0 },
-                    GridDirection::UpRight{ x: position.0 + 1, y: 0 }
-                ];
-
-            },
-            PositionDescription::BottomBound => {
-                search = vec![
-                    GridDirection::Up{ x: position.0, y: position.1 + 1 },
-                    GridDirection::Left{ x: position.0 - 1, y: position.1 },
-                    GridDirection::Right{ x: position.0 + 1, y: position.1 },
-                    GridDirection::UpLeft{ x: position.0 - 1, y: position.1 + 1 },
-                    GridDirection::UpRight{ x: position.0 + 1, y: position.1 + 1 },
-
-                    // wrap around
-                    GridDirection::Down{ x: position.0, y: dimensions.1 },
-                    GridDirection::DownLeft{ x: position.0 - 1, y: dimensions.1 },
-                    GridDirection::DownRight{ x: position.0 + 1, y: dimensions.1 }
-
-                ];
-
-            },
-            PositionDescription::LeftBound => {
-                search = vec![
-                    GridDirection::Up{ x: position.0, y: position.1 + 1 },
-                    GridDirection::Down{ x: position.0, y: position.1 - 1 },
-                    GridDirection::Right{ x: position.0 + 1, y: position.1 },
-                    GridDirection::UpRight{ x: position.0 + 1, y: position.1 + 1 },
-                    GridDirection::DownRight{ x: position.0 + 1, y: position.1 -1 },
-
-                    // wrap around
-                    GridDirection::Left{ x: dimensions.0, y: position.1 },
-                    GridDirection::UpLeft{ x: dimensions.0, y: position.1 + 1 },
-                    GridDirection::DownLeft{ x: dimensions.0, y: position.1 - 1 }
-
-                ];
-
-            },
-            PositionDescription::RightBound => {
-                search = vec![
-                    GridDirection::Up{ x: position.0, y: position.1 + 1 },
-                    GridDirection::Down{ x: position.0, y: position.1 - 1 },
-                    GridDirection::Left{ x: position.0 - 1, y: position.1 },
-                    GridDirection::UpLeft{ x: position.0 - 1, y: position.1 + 1 },
-                    GridDirection::DownLeft{ x: position.0 - 1, y: position.1 - 1 },
-
-                    // wrap around
-                    GridDirection::Right{ x: 0, y: position.1 },
-                    GridDirection::UpRight{ x: 0, y: position.1 + 1 },
-                    GridDirection::DownRight{ x: 0, y: position.1 -1 },
-                ];
-
-            },
-            PositionDescription::TopRightCorner => {
-                search = vec![
-                    GridDirection::Down{ x: position.0, y: position.1 - 1 },
-                    GridDirection::Left{ x: position.0 - 1, y: position.1 },
-                    GridDirection::DownLeft{ x: position.0 - 1, y: position.1 - 1 },
-
-                    // wrap around
-                    GridDirection::Up{ x: position.0, y: 0 },
-                    GridDirection::UpLeft{ x: position.0 - 1, y: 0 },
-                    GridDirection::UpRight{ x: 0, y: 0 },
-                    GridDirection::Right{ x: 0, y: position.1 },
-                    GridDirection::DownRight{ x: 0, y: position.1 - 1 },
-                ];
-
-            },
-            PositionDescription::TopLeftCorner => {
-                search = vec![
-                    GridDirection::Down{ x: position.0, y: position.1 - 1 },
-                    GridDirection::Right{ x: position.0 + 1, y: position.1 },
-                    GridDirection::DownRight{ x: position.0 + 1, y: position.1 -1 },
-
-                    // wrap around
-                    GridDirection::Up{ x: position.0, y: 0 },
-                    GridDirection::Left{ x: dimensions.0, y: position.1 },
-                    GridDirection::UpLeft{ x: dimensions.0 , y: 0 },
-                    GridDirection::UpRight{ x: position.0 + 1, y: 0 },
-                    GridDirection::DownLeft{ x: dimensions.0, y: position.1 - 1 },
-                ];
-
-            },
-            PositionDescription::BottomRightCorner => {
-                search = vec![
-                    GridDirection::Up{ x: position.0, y: position.1 + 1 },
-                    GridDirection::Left{ x: position.0 - 1, y: position.1 },
-                    GridDirection::UpLeft{ x: position.0 - 1, y: position.1 + 1 },
-
-                    // wrap around
-                    GridDirection::Down{ x: position.0, y: dimensions.1 },
-                    GridDirection::Right{ x: 0, y: position.1 },
-                    GridDirection::UpRight{ x: 0, y: position.1 + 1 },
-                    GridDirection::DownLeft{ x: position.0 - 1, y: dimensions.1 },
-                    GridDirection::DownRight{ x: 0, y: dimensions.1 }
-
-                ];
-
-            },
-            PositionDescription::BottomLeftCorner => {
-                search = vec![
-                    GridDirection::Up{ x: position.0, y: position.1 + 1 },
-                    GridDirection::Right{ x: position.0 + 1, y: position.1 },
-                    GridDirection::UpRight{ x: position.0 + 1, y: position.1 + 1 },
-
-                    // wrap around
-                    GridDirection::Down{ x: position.0, y: dimensions.1 },
-                    GridDirection::Left{ x: dimensions.0, y: position.1 },
-                    GridDirection::UpLeft{ x: dimensions.0, y: position.1 + 1 },
-                    GridDirection::DownLeft{ x: dimensions.0, y: dimensions.1 },
-                    GridDirection::DownRight{ x: position.0 + 1, y: dimensions.1 }
-                ];
+        let (x, y) = *position;
+        let mut alive = 0;
+
+        if x > 0 && y > 0 && x + 1 < self.size.0 && y + 1 < self.size.1 {
 
+            // interior cells never need the topology
+            for j in (y - 1)..=(y + 1) {
+                for i in (x - 1)..=(x + 1) {
+                    if (i, j) != (x, y) && self.is_alive(&(i, j)) {
+                        alive += 1;
+                    }
+                }
+            }
+
+        } else {
+
+            for neighbor in self.get_neighbors(position)? {
+                if self.is_alive(&neighbor) {
+                    alive += 1;
+                }
             }
+
+        }
+
+        Ok(alive)
+
+    }
+
+}
+
+// Mutable view of a band of rows, positions are still given in whole-grid coordinates
+pub struct GridBand<'a> {
+    rows: Range<usize>,
+    width: usize,
+    words_per_row: usize,
+    state: &'a mut [u64]
+}
+
+impl GridBand<'_> {
+
+    pub fn rows(&self) -> Range<usize> {
+        self.rows.clone()
+    }
+
+    // Copies one row of a tile from a grid of the same size
+    pub fn copy_tile_row(&mut self, source: &Grid, tile_column: usize, y: usize) {
+
+        if tile_column < self.words_per_row && self.rows.contains(&y) {
+            let word = (y - self.rows.start) * self.words_per_row + tile_column;
+            self.state[word] = source.state[y * source.words_per_row + tile_column];
+        }
+
+    }
+
+    pub fn set_state(&mut self, position: &(usize, usize), state: cell::CellState) -> Result<(), OutOfBoundsError> {
+
+        if position.0 >= self.width || !self.rows.contains(&position.1) {
+            return Err(OutOfBoundsError);
         }
 
+        let word = (position.1 - self.rows.start) * self.words_per_row + position.0 / 64;
+        let bit = position.0 % 64;
+        match state {
+            cell::CellState::Alive => self.state[word] |= 1 << bit,
+            cell::CellState::Dead => self.state[word] &= !(1 << bit)
+        }
 
-        Ok(search)
+        Ok(())
 
     }
 
 }
 
+// grids are equal when they have the same size and the same living cells
+impl PartialEq for Grid {
+    fn eq(&self, other: &Self) -> bool {
+        self.size == other.size && self.state == other.state
+    }
+}
+
 // tui-rs integration
 impl Shape for Grid {
 
     fn draw(&self, painter: &mut Painter) {
 
-        for (position, cell) in self.state.iter() {
-            match cell.get_state() {
-                cell::CellState::Alive => painter.paint(position.0, position.1, self.color),
-                cell::CellState::Dead => continue
-            }
+        for position in self.live_cells() {
+            painter.paint(position.0, position.1, self.color);
         }
 
     }
@@ -391,7 +531,6 @@ impl Shape for Grid {
 
 
 
-
 #[cfg(test)]
 
 #[test]
@@ -411,10 +550,189 @@ fn test_grid_update() {
 
     new_grid.update(seed);
 
+    assert!(new_grid.is_alive(&(3,3)));
+    assert_eq!(new_grid.population(), 1);
+
 }
 
+#[test]
+fn test_grid_bit_storage() {
+
+    // 70 columns spill over into a second word per row
+    let mut new_grid = Grid::new((70,3));
 
+    let positions = vec![(0,0), (63,0), (64,1), (69,2)];
+    for position in positions.iter() {
+        new_grid.set_state(position, cell::CellState::Alive).unwrap();
+    }
 
+    assert_eq!(new_grid.live_cells(), positions);
+    assert_eq!(*new_grid.get_cell(&(64,1)).unwrap().get_state(), cell::CellState::Alive);
+    assert_eq!(*new_grid.get_cell(&(64,0)).unwrap().get_state(), cell::CellState::Dead);
+    assert!(new_grid.get_cell(&(70,0)).is_none());
+    assert!(new_grid.set_state(&(0,3), cell::CellState::Alive).is_err());
 
+    new_grid.set_state(&(63,0), cell::CellState::Dead).unwrap();
+    assert_eq!(new_grid.population(), 3);
 
+    new_grid.clear();
+    assert_eq!(new_grid.population(), 0);
 
+}
+
+#[test]
+fn test_grid_count_living_neighbors() {
+
+    let mut new_grid = Grid::new((10,10));
+
+    let mut seed: HashMap<(usize,usize), cell::CellState> = HashMap::new();
+    seed.entry((0,0)).or_insert(cell::CellState::Alive);
+    seed.entry((1,1)).or_insert(cell::CellState::Alive);
+    seed.entry((9,9)).or_insert(cell::CellState::Alive);
+    new_grid.update(seed);
+
+    assert_eq!(new_grid.count_living_neighbors(&(1,0)).unwrap(), 2);
+    assert_eq!(new_grid.count_living_neighbors(&(2,2)).unwrap(), 1);
+
+    // (9,9) is only a neighbor of (0,0) because the default grid wraps around
+    assert_eq!(new_grid.count_living_neighbors(&(0,0)).unwrap(), 2);
+    assert!(new_grid.count_living_neighbors(&(10,0)).is_err());
+
+}
+
+#[test]
+fn test_grid_births_and_deaths() {
+
+    let mut first = Grid::new((70, 3));
+    let mut second = Grid::new((70, 3));
+
+    for position in [(0, 0), (65, 1), (69, 2)].iter() {
+        first.set_state(position, cell::CellState::Alive).unwrap();
+    }
+    for position in [(65, 1), (1, 0), (2, 0), (68, 2)].iter() {
+        second.set_state(position, cell::CellState::Alive).unwrap();
+    }
+
+    assert_eq!(first.births_and_deaths(&second), (3, 2));
+    assert_eq!(first.births_and_deaths(&first), (0, 0));
+
+}
+
+#[test]
+fn test_grid_bands() {
+
+    let mut new_grid = Grid::new((70,10));
+
+    {
+        let mut bands = new_grid.bands_mut(3);
+        let rows: Vec<Range<usize>> = bands.iter().map(|band| band.rows()).collect();
+        assert_eq!(rows, vec![0..4, 4..8, 8..10]);
+
+        bands[1].set_state(&(65,5), cell::CellState::Alive).unwrap();
+        assert!(bands[1].set_state(&(65,8), cell::CellState::Alive).is_err());
+    }
+
+    assert_eq!(new_grid.live_cells(), vec![(65,5)]);
+
+    // never more bands than rows
+    assert_eq!(new_grid.bands_mut(50).len(), 10);
+
+}
+
+#[test]
+fn test_grid_changed_tiles() {
+
+    // 2 x 3 tiles
+    let new_grid = Grid::new((100,40));
+    let mut other = new_grid.clone();
+
+    other.set_state(&(70,17), cell::CellState::Alive).unwrap();
+    other.set_state(&(3,39), cell::CellState::Alive).unwrap();
+
+    assert_eq!(new_grid.tile_count(), (2,3));
+    assert_eq!(new_grid.changed_tiles(&other), vec![false, false, false, true, true, false]);
+
+}
+
+#[test]
+fn test_grid_region_copy_and_paste() {
+
+    // an L of four cells in a 3x2 region
+    let mut new_grid = Grid::new((10,10));
+    for position in [(2,3), (2,4), (3,4), (4,4)].iter() {
+        new_grid.set_state(position, cell::CellState::Alive).unwrap();
+    }
+    let region = Region::new((4,4), (2,3));
+    assert_eq!((region.min, region.size()), ((2,3), (3,2)));
+
+    let copy = new_grid.copy_region(&region).unwrap();
+    assert_eq!(*copy.get_size(), (3,2));
+    assert_eq!(copy.live_cells(), vec![(0,0), (0,1), (1,1), (2,1)]);
+
+    // pasting overwrites dead cells too
+    new_grid.set_state(&(8,6), cell::CellState::Alive).unwrap();
+    new_grid.paste(&copy, (6,6)).unwrap();
+    assert_eq!(new_grid.population(), 8);
+    assert!(!new_grid.is_alive(&(8,6)));
+    assert!(new_grid.paste(&copy, (8,8)).is_err());
+    assert_eq!(new_grid.population(), 8);
+
+    let cut = new_grid.cut_region(&Region::new((6,6), (8,7))).unwrap();
+    assert_eq!(cut, copy);
+    assert_eq!(new_grid.population(), 4);
+    assert!(new_grid.copy_region(&Region::new((5,5), (10,5))).is_err());
+
+}
+
+#[test]
+fn test_grid_region_transforms() {
+
+    let mut new_grid = Grid::new((3,2));
+    for position in [(0,0), (0,1), (1,1), (2,1)].iter() {
+        new_grid.set_state(position, cell::CellState::Alive).unwrap();
+    }
+
+    let rotated = new_grid.rotated();
+    assert_eq!(*rotated.get_size(), (2,3));
+    assert_eq!(rotated.live_cells(), vec![(0,0), (1,0), (0,1), (0,2)]);
+    assert_eq!(rotated.rotated().rotated().rotated(), new_grid);
+    assert_eq!(new_grid.flipped_horizontally().live_cells(), vec![(2,0), (0,1), (1,1), (2,1)]);
+    assert_eq!(new_grid.flipped_vertically().live_cells(), vec![(0,0), (1,0), (2,0), (0,1)]);
+    assert_eq!(new_grid.flipped_horizontally().flipped_vertically(), new_grid.rotated().rotated());
+
+    // in place, a wide region turns into a tall one and leaves nothing behind
+    let mut board = Grid::new((8,8));
+    board.paste(&new_grid, (1,1)).unwrap();
+    let turned = board.rotate_region(&Region::new((1,1), (3,2))).unwrap();
+    assert_eq!(turned, Region::new((1,1), (2,3)));
+    assert_eq!(board.copy_region(&turned).unwrap(), rotated);
+    assert_eq!(board.population(), 4);
+    assert!(board.rotate_region(&Region::new((0,6), (7,7))).is_err());
+
+    board.flip_region_vertically(&turned).unwrap();
+    board.flip_region_horizontally(&turned).unwrap();
+    assert_eq!(board.copy_region(&turned).unwrap(), rotated.rotated().rotated());
+
+}
+
+#[test]
+fn test_grid_region_fill() {
+
+    let mut new_grid = Grid::new((20,20));
+    let region = Region::new((2,2), (11,11));
+
+    new_grid.fill_region(&region, cell::CellState::Alive).unwrap();
+    assert_eq!(new_grid.population(), 100);
+    new_grid.fill_region(&Region::new((2,2), (11,6)), cell::CellState::Dead).unwrap();
+    assert_eq!(new_grid.population(), 50);
+
+    // the same seed gives the same cells, none outside the region
+    let mut other = new_grid.clone();
+    new_grid.randomize_region(&region, 0.5, &mut Random::new(3)).unwrap();
+    other.randomize_region(&region, 0.5, &mut Random::new(3)).unwrap();
+    assert_eq!(new_grid, other);
+    assert!(new_grid.population() > 25 && new_grid.population() < 75);
+    assert!(new_grid.live_cells().iter().all(|position| region.contains(position)));
+    assert!(new_grid.fill_region(&Region::new((0,0), (20,0)), cell::CellState::Alive).is_err());
+
+}
@@ -0,0 +1,890 @@
+
+use std::collections::HashMap;
+use std::fmt;
+
+use crate::cell;
+use crate::game;
+use crate::grid;
+use crate::history;
+use crate::rule;
+use crate::universe;
+use tui::{
+    style::Color,
+    widgets::canvas::{Painter, Shape},
+};
+
+// Index of a node in the arena
+type NodeId = u32;
+
+const DEAD: NodeId = 0;
+const ALIVE: NodeId = 1;
+
+// Nodes past this level would have corners that no longer fit in an i64
+const MAX_LEVEL: u8 = 60;
+
+// Largest number of generations advanced in a single step, as a power of two. A step needs the
+// root two levels above it, plus one more level of padding
+pub const MAX_EXPONENT: u8 = MAX_LEVEL - 3;
+
+// Default number of nodes kept before the cache is garbage collected
+const DEFAULT_CACHE_LIMIT: usize = 1 << 22;
+
+// Quadtree node. A node of level k covers a 2^k x 2^k square; its children are the nw, ne, sw
+// and se quadrants with y growing downwards. Level 0 nodes are single cells
+#[derive(Debug, Copy, Clone)]
+struct Node {
+    level: u8,
+    children: [NodeId; 4],
+    population: u64
+}
+
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum HashLifeError {
+    // B0 rules would bring the empty space around the pattern to life, which is never advanced
+    BirthOnZero,
+    // the pattern or the generation count grew past what can be represented
+    TooFar
+}
+
+impl fmt::Display for HashLifeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            HashLifeError::BirthOnZero => write!(f, "Rules with B0 can not run on HashLife"),
+            HashLifeError::TooFar => write!(f, "The pattern grew past the edge of the HashLife universe")
+        }
+    }
+}
+
+impl std::error::Error for HashLifeError {}
+
+// HashLife engine. Identical subtrees are stored once and the result of advancing each one is
+// memoized, which lets large regular patterns skip ahead by huge numbers of generations. The
+// root is always centered on the origin
+pub struct HashLife {
+    evolution: u64,
+    rule: rule::Rule,
+    nodes: Vec<Node>,
+    canonical: HashMap<[NodeId; 4], NodeId>,
+    results: HashMap<(NodeId, u8), NodeId>,
+    empty: Vec<NodeId>,
+    root: NodeId,
+    cache_limit: usize,
+    history: history::PopulationHistory,
+    color: Color
+}
+
+impl Default for HashLife {
+
+    // same seed as the default finite grid
+    fn default() -> Self {
+        let universe = universe::Universe::from(&game::GameOfLife::default().cell_grid);
+        Self::from_universe(&universe, rule::Rule::default()).expect("Conway's rule has no B0")
+    }
+
+}
+
+impl HashLife {
+
+    pub fn new(rule: rule::Rule) -> Result<Self, HashLifeError> {
+
+        if rule.is_born(0) {
+            return Err(HashLifeError::BirthOnZero);
+        }
+
+        let mut hashlife = Self {
+            evolution: 0,
+            rule,
+            nodes: Vec::new(),
+            canonical: HashMap::new(),
+            results: HashMap::new(),
+            empty: Vec::new(),
+            root: DEAD,
+            cache_limit: DEFAULT_CACHE_LIMIT,
+            history: history::PopulationHistory::new(),
+            color: Color::Cyan
+        };
+
+        hashlife.reset_arena();
+        hashlife.root = hashlife.empty_node(3);
+        Ok(hashlife)
+
+    }
+
+    pub fn from_universe(universe: &universe::Universe, rule: rule::Rule) -> Result<Self, HashLifeError> {
+
+        let mut hashlife = Self::new(rule)?;
+        for position in universe.live_cells() {
+            hashlife.set_state(&position, cell::CellState::Alive)?;
+        }
+
+        Ok(hashlife)
+
+    }
+
+    // Upper bound on the number of cached nodes. The cache is collected between steps, so a
+    // single very large step may temporarily exceed it
+    pub fn with_cache_limit(mut self, cache_limit: usize) -> Self {
+        self.cache_limit = cache_limit;
+        self
+    }
+
+    pub fn cache_size(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn set_rule(&mut self, rule: rule::Rule) -> Result<(), HashLifeError> {
+
+        if rule.is_born(0) {
+            return Err(HashLifeError::BirthOnZero);
+        }
+
+        // memoized results only hold for the rule they were computed with
+        self.rule = rule;
+        self.results.clear();
+        Ok(())
+
+    }
+
+    pub fn to_universe(&self) -> universe::Universe {
+
+        let mut universe = universe::Universe::new();
+        for position in self.live_cells() {
+            universe.set_state(&position, cell::CellState::Alive);
+        }
+
+        universe
+
+    }
+
+    // Cells so far out that the root would reach MAX_LEVEL are out of reach
+    pub fn set_state(&mut self, position: &(i64, i64), state: cell::CellState) -> Result<(), HashLifeError> {
+
+        while !self.covers(position) {
+            if self.nodes[self.root as usize].level + 1 >= MAX_LEVEL {
+                return Err(HashLifeError::TooFar);
+            }
+            self.root = self.expand(self.root);
+        }
+
+        let level = self.nodes[self.root as usize].level;
+        let half = 1i64 << (level - 1);
+        let local = ((position.0 + half) as u64, (position.1 + half) as u64);
+
+        let leaf = match state {
+            cell::CellState::Alive => ALIVE,
+            cell::CellState::Dead => DEAD
+        };
+
+        self.root = self.set_leaf(self.root, local, leaf);
+        Ok(())
+
+    }
+
+    pub fn is_alive(&self, position: &(i64, i64)) -> bool {
+
+        if !self.covers(position) {
+            return false;
+        }
+
+        let mut node = self.root;
+        let level = self.nodes[node as usize].level;
+        let half = 1i64 << (level - 1);
+        let (x, y) = ((position.0 + half) as u64, (position.1 + half) as u64);
+
+        for level in (0..level).rev() {
+
+            if self.nodes[node as usize].population == 0 {
+                return false;
+            }
+
+            let quadrant = ((x >> level) & 1) + 2 * ((y >> level) & 1);
+            node = self.nodes[node as usize].children[quadrant as usize];
+
+        }
+
+        node == ALIVE
+
+    }
+
+    pub fn live_cells(&self) -> Vec<(i64, i64)> {
+
+        let level = self.nodes[self.root as usize].level;
+        let half = 1i64 << (level - 1);
+
+        let mut cells = Vec::new();
+        self.collect_cells(self.root, (-half, -half), None, &mut cells);
+        cells
+
+    }
+
+    // living cells inside a window, skipping every subtree that lies outside of it
+    pub fn cells_in(&self, window: &universe::BoundingBox) -> Vec<(i64, i64)> {
+
+        let level = self.nodes[self.root as usize].level;
+        let half = 1i64 << (level - 1);
+
+        let mut cells = Vec::new();
+        self.collect_cells(self.root, (-half, -half), Some(window), &mut cells);
+        cells
+
+    }
+
+    // Advances the universe by 2^exponent generations. Exponents past MAX_EXPONENT are split
+    // into several of the largest steps. On an error the universe stays at the last step that
+    // could be taken
+    pub fn advance_pow2(&mut self, exponent: u8) -> Result<(), HashLifeError> {
+
+        if exponent >= 64 {
+            return Err(HashLifeError::TooFar);
+        }
+
+        if exponent > MAX_EXPONENT {
+            for _ in 0..(1u64 << (exponent - MAX_EXPONENT)) {
+                self.step_pow2(MAX_EXPONENT)?;
+            }
+            return Ok(());
+        }
+
+        self.step_pow2(exponent)
+
+    }
+
+    // Advances the universe by any number of generations, one power of two at a time
+    pub fn advance(&mut self, generations: u64) -> Result<(), HashLifeError> {
+
+        for exponent in 0..64 {
+            if generations & (1 << exponent) != 0 {
+                self.advance_pow2(exponent)?;
+            }
+        }
+
+        Ok(())
+
+    }
+
+    // Advances the universe by 2^exponent generations in a single recursive step, exponent being
+    // at most MAX_EXPONENT
+    fn step_pow2(&mut self, exponent: u8) -> Result<(), HashLifeError> {
+
+        let evolution = self.evolution.checked_add(1 << exponent).ok_or(HashLifeError::TooFar)?;
+        self.history.record(self.evolution, self.nodes[self.root as usize].population as usize);
+
+        // pad until the pattern sits well inside the root, then once more so nothing it grows into
+        // during the step falls outside the result
+        while self.nodes[self.root as usize].level < (exponent + 2).max(3) || !self.is_padded(self.root) {
+            if self.nodes[self.root as usize].level + 1 >= MAX_LEVEL {
+                return Err(HashLifeError::TooFar);
+            }
+            self.root = self.expand(self.root);
+        }
+
+        let padded = self.expand(self.root);
+        self.root = self.next(padded, exponent);
+        self.evolution = evolution;
+        self.history.record(self.evolution, self.nodes[self.root as usize].population as usize);
+
+        self.collect_garbage();
+        Ok(())
+
+    }
+
+    fn reset_arena(&mut self) {
+
+        self.nodes = vec![
+            Node { level: 0, children: [DEAD; 4], population: 0 },
+            Node { level: 0, children: [DEAD; 4], population: 1 }
+        ];
+        self.canonical.clear();
+        self.results.clear();
+        self.empty = vec![DEAD];
+
+    }
+
+    // Returns the unique node with the given children, creating it if it does not exist yet
+    fn join(&mut self, children: [NodeId; 4]) -> NodeId {
+
+        if let Some(id) = self.canonical.get(&children) {
+            return *id;
+        }
+
+        let level = self.nodes[children[0] as usize].level + 1;
+        let population = children.iter().map(|child| self.nodes[*child as usize].population).sum();
+
+        let id = self.nodes.len() as NodeId;
+        self.nodes.push(Node { level, children, population });
+        self.canonical.insert(children, id);
+        id
+
+    }
+
+    fn empty_node(&mut self, level: u8) -> NodeId {
+
+        while self.empty.len() <= level as usize {
+            let child = self.empty[self.empty.len() - 1];
+            let node = self.join([child; 4]);
+            self.empty.push(node);
+        }
+
+        self.empty[level as usize]
+
+    }
+
+    fn child(&self, node: NodeId, quadrant: usize) -> NodeId {
+        self.nodes[node as usize].children[quadrant]
+    }
+
+    fn covers(&self, position: &(i64, i64)) -> bool {
+
+        let level = self.nodes[self.root as usize].level;
+        let half = 1i64 << (level - 1);
+        position.0 >= -half && position.0 < half && position.1 >= -half && position.1 < half
+
+    }
+
+    // Surrounds a node with empty space, doubling its size while keeping the same center
+    fn expand(&mut self, node: NodeId) -> NodeId {
+
+        let level = self.nodes[node as usize].level;
+        let e = self.empty_node(level - 1);
+        let [nw, ne, sw, se] = self.nodes[node as usize].children;
+
+        let nw = self.join([e, e, e, nw]);
+        let ne = self.join([e, e, ne, e]);
+        let sw = self.join([e, sw, e, e]);
+        let se = self.join([se, e, e, e]);
+        self.join([nw, ne, sw, se])
+
+    }
+
+    // true when every living cell is within the central quarter of the node
+    fn is_padded(&self, node: NodeId) -> bool {
+
+        let [nw, ne, sw, se] = self.nodes[node as usize].children;
+        let inner = |quadrant: NodeId, towards_center: usize| {
+            let inner = self.child(self.child(quadrant, towards_center), towards_center);
+            self.nodes[quadrant as usize].population == self.nodes[inner as usize].population
+        };
+
+        inner(nw, 3) && inner(ne, 2) && inner(sw, 1) && inner(se, 0)
+
+    }
+
+    fn set_leaf(&mut self, node: NodeId, local: (u64, u64), leaf: NodeId) -> NodeId {
+
+        let level = self.nodes[node as usize].level;
+        if level == 0 {
+            return leaf;
+        }
+
+        let shift = level - 1;
+        let quadrant = (((local.0 >> shift) & 1) + 2 * ((local.1 >> shift) & 1)) as usize;
+
+        let mut children = self.nodes[node as usize].children;
+        children[quadrant] = self.set_leaf(children[quadrant], local, leaf);
+        self.join(children)
+
+    }
+
+    fn collect_cells(&self, node: NodeId, corner: (i64, i64), window: Option<&universe::BoundingBox>,
+        cells: &mut Vec<(i64, i64)>) {
+
+        let current = self.nodes[node as usize];
+        if current.population == 0 {
+            return;
+        }
+
+        if let Some(window) = window {
+            let far = (1i64 << current.level) - 1;
+            if corner.0 > window.max.0 || corner.1 > window.max.1 ||
+                corner.0 + far < window.min.0 || corner.1 + far < window.min.1 {
+                return;
+            }
+        }
+
+        if current.level == 0 {
+            cells.push(corner);
+            return;
+        }
+
+        let half = 1i64 << (current.level - 1);
+        for (quadrant, child) in current.children.iter().enumerate() {
+            let offset = ((quadrant % 2) as i64 * half, (quadrant / 2) as i64 * half);
+            self.collect_cells(*child, (corner.0 + offset.0, corner.1 + offset.1), window, cells);
+        }
+
+    }
+
+    // Returns the central half of a node advanced by 2^exponent generations, where the exponent
+    // is at most level - 2
+    fn next(&mut self, node: NodeId, exponent: u8) -> NodeId {
+
+        let level = self.nodes[node as usize].level;
+
+        if self.nodes[node as usize].population == 0 {
+            return self.empty_node(level - 1);
+        }
+
+        if let Some(result) = self.results.get(&(node, exponent)) {
+            return *result;
+        }
+
+        let result = if level == 2 {
+            self.next_generation_4x4(node)
+        } else {
+
+            let [nw, ne, sw, se] = self.nodes[node as usize].children;
+            let c = |hashlife: &Self, parent: NodeId, quadrant: usize| hashlife.child(parent, quadrant);
+
+            // nine overlapping subnodes of half the size
+            let n00 = nw;
+            let n01 = self.join([c(self, nw, 1), c(self, ne, 0), c(self, nw, 3), c(self, ne, 2)]);
+            let n02 = ne;
+            let n10 = self.join([c(self, nw, 2), c(self, nw, 3), c(self, sw, 0), c(self, sw, 1)]);
+            let n11 = self.join([c(self, nw, 3), c(self, ne, 2), c(self, sw, 1), c(self, se, 0)]);
+            let n12 = self.join([c(self, ne, 2), c(self, ne, 3), c(self, se, 0), c(self, se, 1)]);
+            let n20 = sw;
+            let n21 = self.join([c(self, sw, 1), c(self, se, 0), c(self, sw, 3), c(self, se, 2)]);
+            let n22 = se;
+
+            let subnodes = [n00, n01, n02, n10, n11, n12, n20, n21, n22];
+            let mut r = [DEAD; 9];
+
+            if exponent == level - 2 {
+
+                // full speed: each half of the step is done by one level of the recursion
+                for (index, subnode) in subnodes.iter().enumerate() {
+                    r[index] = self.next(*subnode, exponent - 1);
+                }
+
+                let remaining = exponent - 1;
+                self.combine(r, remaining)
+
+            } else {
+
+                // slower steps only advance the second time around
+                for (index, subnode) in subnodes.iter().enumerate() {
+                    r[index] = self.center(*subnode);
+                }
+
+                self.combine(r, exponent)
+
+            }
+
+        };
+
+        self.results.insert((node, exponent), result);
+        result
+
+    }
+
+    // Joins nine subresults into four overlapping nodes, advances each and joins the results
+    fn combine(&mut self, r: [NodeId; 9], exponent: u8) -> NodeId {
+
+        let nw = self.join([r[0], r[1], r[3], r[4]]);
+        let ne = self.join([r[1], r[2], r[4], r[5]]);
+        let sw = self.join([r[3], r[4], r[6], r[7]]);
+        let se = self.join([r[4], r[5], r[7], r[8]]);
+
+        let nw = self.next(nw, exponent);
+        let ne = self.next(ne, exponent);
+        let sw = self.next(sw, exponent);
+        let se = self.next(se, exponent);
+        self.join([nw, ne, sw, se])
+
+    }
+
+    fn center(&mut self, node: NodeId) -> NodeId {
+
+        let [nw, ne, sw, se] = self.nodes[node as usize].children;
+        let children = [self.child(nw, 3), self.child(ne, 2), self.child(sw, 1), self.child(se, 0)];
+        self.join(children)
+
+    }
+
+    // Base case: applies the rule once to the central 2x2 cells of a 4x4 node
+    fn next_generation_4x4(&mut self, node: NodeId) -> NodeId {
+
+        let mut cells = [[false; 4]; 4];
+        for (quadrant, child) in self.nodes[node as usize].children.iter().enumerate() {
+            for (index, leaf) in self.nodes[*child as usize].children.iter().enumerate() {
+                let x = (quadrant % 2) * 2 + index % 2;
+                let y = (quadrant / 2) * 2 + index / 2;
+                cells[y][x] = *leaf == ALIVE;
+            }
+        }
+
+        let mut result = [DEAD; 4];
+        for (index, leaf) in result.iter_mut().enumerate() {
+
+            let (x, y) = (1 + index % 2, 1 + index / 2);
+            let mut alive = 0;
+            for (j, row) in cells.iter().enumerate().skip(y - 1).take(3) {
+                for (i, neighbor) in row.iter().enumerate().skip(x - 1).take(3) {
+                    if (i, j) != (x, y) && *neighbor {
+                        alive += 1;
+                    }
+                }
+            }
+
+            let state = if cells[y][x] { cell::CellState::Alive } else { cell::CellState::Dead };
+            if self.rule.next_state(&state, alive) == cell::CellState::Alive {
+                *leaf = ALIVE;
+            }
+
+        }
+
+        self.join(result)
+
+    }
+
+    // Once the cache outgrows its limit, every node that is not part of the current universe is
+    // dropped along with all memoized results
+    fn collect_garbage(&mut self) {
+
+        if self.nodes.len() <= self.cache_limit {
+            return;
+        }
+
+        let old_nodes = std::mem::take(&mut self.nodes);
+        self.reset_arena();
+
+        let mut remapped: HashMap<NodeId, NodeId> = HashMap::new();
+        remapped.insert(DEAD, DEAD);
+        remapped.insert(ALIVE, ALIVE);
+        self.root = self.copy_node(&old_nodes, self.root, &mut remapped);
+
+    }
+
+    fn copy_node(&mut self, old_nodes: &[Node], node: NodeId, remapped: &mut HashMap<NodeId, NodeId>) -> NodeId {
+
+        if let Some(id) = remapped.get(&node) {
+            return *id;
+        }
+
+        let mut children = old_nodes[node as usize].children;
+        for child in children.iter_mut() {
+            *child = self.copy_node(old_nodes, *child, remapped);
+        }
+
+        let id = self.join(children);
+        remapped.insert(node, id);
+        id
+
+    }
+
+    // position of the outermost living cell along one side, relative to the node's corner
+    fn extent(&self, node: NodeId, side: Side, memo: &mut HashMap<NodeId, i64>) -> i64 {
+
+        if let Some(extent) = memo.get(&node) {
+            return *extent;
+        }
+
+        let current = self.nodes[node as usize];
+        if current.level == 0 {
+            return 0;
+        }
+
+        let half = 1i64 << (current.level - 1);
+
+        // quadrants on the side we are looking for first, then the ones behind them
+        let (near, far) = match side {
+            Side::Left => ([0, 2], [1, 3]),
+            Side::Right => ([1, 3], [0, 2]),
+            Side::Top => ([0, 1], [2, 3]),
+            Side::Bottom => ([2, 3], [0, 1])
+        };
+
+        let offset = |quadrant: usize| match side {
+            Side::Left | Side::Right => (quadrant % 2) as i64 * half,
+            Side::Top | Side::Bottom => (quadrant / 2) as i64 * half
+        };
+
+        let mut candidates = Vec::new();
+        for group in [near, far].iter() {
+
+            for quadrant in group.iter() {
+                let child = current.children[*quadrant];
+                if self.nodes[child as usize].population > 0 {
+                    candidates.push(self.extent(child, side, memo) + offset(*quadrant));
+                }
+            }
+
+            if !candidates.is_empty() {
+                break;
+            }
+
+        }
+
+        let extent = match side {
+            Side::Left | Side::Top => candidates.into_iter().min().unwrap_or(0),
+            Side::Right | Side::Bottom => candidates.into_iter().max().unwrap_or(0)
+        };
+
+        memo.insert(node, extent);
+        extent
+
+    }
+
+}
+
+#[derive(Debug, Copy, Clone)]
+enum Side {
+    Left,
+    Right,
+    Top,
+    Bottom
+}
+
+impl game::Simulation for HashLife {
+
+    // a pattern growing past the edge of the universe is out of bounds like on a finite grid
+    fn update(&mut self) -> Result<(), grid::OutOfBoundsError> {
+        self.advance_pow2(0).map_err(|_| grid::OutOfBoundsError)
+    }
+
+    fn advance(&mut self, generations: u64) -> Result<(), grid::OutOfBoundsError> {
+        HashLife::advance(self, generations).map_err(|_| grid::OutOfBoundsError)
+    }
+
+    fn skips_ahead(&self) -> bool {
+        true
+    }
+
+    fn get_evolution(&self) -> u64 {
+        self.evolution
+    }
+
+    fn set_evolution(&mut self, evolution: u64) {
+        self.evolution = evolution;
+    }
+
+    fn get_rule(&self) -> &rule::Rule {
+        &self.rule
+    }
+
+    fn population(&self) -> usize {
+        self.nodes[self.root as usize].population as usize
+    }
+
+    fn bounding_box(&self) -> Option<universe::BoundingBox> {
+
+        if self.nodes[self.root as usize].population == 0 {
+            return None;
+        }
+
+        let level = self.nodes[self.root as usize].level;
+        let half = 1i64 << (level - 1);
+
+        let mut extents = [0; 4];
+        for (extent, side) in extents.iter_mut().zip([Side::Left, Side::Top, Side::Right, Side::Bottom].iter()) {
+            *extent = self.extent(self.root, *side, &mut HashMap::new()) - half;
+        }
+
+        Some(universe::BoundingBox {
+            min: (extents[0], extents[1]),
+            max: (extents[2], extents[3])
+        })
+
+    }
+
+    // single generations are not stepped cell by cell, so births and deaths are unknown
+    fn last_changes(&self) -> Option<game::Changes> {
+        None
+    }
+
+    fn history(&self) -> &history::PopulationHistory {
+        &self.history
+    }
+
+    fn topology_name(&self) -> &'static str {
+        "unbounded"
+    }
+
+    fn limits(&self) -> Option<universe::BoundingBox> {
+        None
+    }
+
+    fn is_alive(&self, position: &(i64, i64)) -> bool {
+        HashLife::is_alive(self, position)
+    }
+
+    fn set_state(&mut self, position: &(i64, i64), state: cell::CellState) -> Result<(), grid::OutOfBoundsError> {
+        HashLife::set_state(self, position, state).map_err(|_| grid::OutOfBoundsError)
+    }
+
+    fn cells_in(&self, window: &universe::BoundingBox) -> Vec<(i64, i64)> {
+        HashLife::cells_in(self, window)
+    }
+
+}
+
+// tui-rs integration, only the part of the universe that can land on the canvas is visited
+impl Shape for HashLife {
+
+    fn draw(&self, painter: &mut Painter) {
+
+        let window = universe::BoundingBox { min: (0, 0), max: (u16::MAX as i64, u16::MAX as i64) };
+        for (x, y) in self.cells_in(&window) {
+            painter.paint(x as usize, y as usize, self.color);
+        }
+
+    }
+
+}
+
+
+// tests
+#[cfg(test)]
+use crate::game::Simulation;
+
+#[cfg(test)]
+fn universe_of(cells: &[(i64, i64)]) -> universe::Universe {
+
+    let mut universe = universe::Universe::new();
+    for position in cells.iter() {
+        universe.set_state(position, cell::CellState::Alive);
+    }
+
+    universe
+
+}
+
+#[cfg(test)]
+fn naive(mut universe: universe::Universe, rule: &rule::Rule, generations: u64) -> universe::Universe {
+
+    for _ in 0..generations {
+        universe = universe.step(rule);
+    }
+
+    universe
+
+}
+
+#[test]
+fn test_hashlife_set_and_get() {
+
+    let mut hashlife = HashLife::new(rule::Rule::default()).unwrap();
+
+    hashlife.set_state(&(-5, 3), cell::CellState::Alive).unwrap();
+    hashlife.set_state(&(1000, -2000), cell::CellState::Alive).unwrap();
+
+    assert!(hashlife.is_alive(&(-5, 3)));
+    assert!(hashlife.is_alive(&(1000, -2000)));
+    assert!(!hashlife.is_alive(&(0, 0)));
+    assert_eq!(hashlife.population(), 2);
+    assert_eq!(hashlife.bounding_box(), Some(universe::BoundingBox { min: (-5, -2000), max: (1000, 3) }));
+
+    hashlife.set_state(&(-5, 3), cell::CellState::Dead).unwrap();
+    assert_eq!(hashlife.live_cells(), vec![(1000, -2000)]);
+
+    // the far corners of the i64 range are out of reach
+    assert_eq!(hashlife.set_state(&(i64::MAX, 0), cell::CellState::Alive), Err(HashLifeError::TooFar));
+    assert_eq!(hashlife.set_state(&(0, i64::MIN), cell::CellState::Alive), Err(HashLifeError::TooFar));
+    assert_eq!(hashlife.live_cells(), vec![(1000, -2000)]);
+
+}
+
+#[test]
+fn test_hashlife_matches_naive_engine() {
+
+    // r-pentomino, chaotic for about a thousand generations
+    let r_pentomino = universe_of(&[(1, 0), (2, 0), (0, 1), (1, 1), (1, 2)]);
+    let rule = rule::Rule::default();
+
+    let mut hashlife = HashLife::from_universe(&r_pentomino, rule).unwrap();
+    let mut expected = r_pentomino;
+
+    for generations in [1, 1, 6, 32, 100, 437].iter() {
+        hashlife.advance(*generations).unwrap();
+        expected = naive(expected, &rule, *generations);
+        assert_eq!(hashlife.to_universe(), expected);
+        assert_eq!(hashlife.bounding_box(), expected.bounding_box());
+    }
+
+    assert_eq!(hashlife.get_evolution(), 577);
+
+}
+
+#[test]
+fn test_hashlife_update_matches_naive_engine() {
+
+    let highlife: rule::Rule = "B36/S23".parse().unwrap();
+    let replicator = universe_of(&[(1, 0), (2, 0), (3, 0), (0, 1), (3, 1), (0, 2), (3, 2), (0, 3), (1, 3), (2, 3)]);
+
+    let mut hashlife = HashLife::from_universe(&replicator, highlife).unwrap();
+    for _ in 0..50 {
+        hashlife.update().unwrap();
+    }
+
+    assert_eq!(hashlife.to_universe(), naive(replicator, &highlife, 50));
+
+}
+
+#[test]
+fn test_hashlife_advance_pow2() {
+
+    let glider = universe_of(&[(1, 0), (2, 1), (0, 2), (1, 2), (2, 2)]);
+
+    // a glider travels one cell every 4 generations, so 2^20 generations move it 2^18 cells
+    let mut hashlife = HashLife::from_universe(&glider, rule::Rule::default()).unwrap();
+    hashlife.advance_pow2(20).unwrap();
+
+    let start = glider.bounding_box().unwrap();
+    let bounds = hashlife.bounding_box().unwrap();
+    assert_eq!(hashlife.population(), 5);
+    assert_eq!(hashlife.get_evolution(), 1 << 20);
+    assert_eq!((bounds.min.0 - start.min.0).abs(), 1 << 18);
+    assert_eq!((bounds.min.1 - start.min.1).abs(), 1 << 18);
+
+}
+
+#[test]
+fn test_hashlife_large_steps() {
+
+    // a block stays put however far ahead it goes, larger steps are split up
+    let block = universe_of(&[(0, 0), (1, 0), (0, 1), (1, 1)]);
+    let mut hashlife = HashLife::from_universe(&block, rule::Rule::default()).unwrap();
+    hashlife.advance_pow2(60).unwrap();
+    assert_eq!(hashlife.get_evolution(), 1 << 60);
+    assert_eq!(hashlife.to_universe(), block);
+
+    hashlife.advance((1 << 62) + 3).unwrap();
+    assert_eq!(hashlife.get_evolution(), (1 << 62) + (1 << 60) + 3);
+    assert_eq!(hashlife.advance(u64::MAX), Err(HashLifeError::TooFar));
+
+    // a glider flies off the edge of the universe long before 2^62 generations
+    let glider = universe_of(&[(1, 0), (2, 1), (0, 2), (1, 2), (2, 2)]);
+    let mut hashlife = HashLife::from_universe(&glider, rule::Rule::default()).unwrap();
+    assert_eq!(hashlife.advance_pow2(62), Err(HashLifeError::TooFar));
+    assert_eq!(hashlife.get_evolution() % (1 << MAX_EXPONENT), 0);
+    assert_eq!(hashlife.population(), 5);
+
+}
+
+#[test]
+fn test_hashlife_rejects_birth_on_zero() {
+
+    let b0: rule::Rule = "B03/S23".parse().unwrap();
+    assert!(matches!(HashLife::new(b0), Err(HashLifeError::BirthOnZero)));
+
+    let mut hashlife = HashLife::new(rule::Rule::default()).unwrap();
+    assert_eq!(hashlife.set_rule(b0), Err(HashLifeError::BirthOnZero));
+    assert_eq!(hashlife.get_rule(), &rule::Rule::default());
+
+}
+
+#[test]
+fn test_hashlife_garbage_collection() {
+
+    let rule = rule::Rule::default();
+    let start = universe::Universe::from(&game::GameOfLife::default().cell_grid);
+
+    // a tiny cache forces a collection after every step
+    let mut hashlife = HashLife::from_universe(&start, rule).unwrap().with_cache_limit(1000);
+    hashlife.advance(300).unwrap();
+
+    assert!(hashlife.cache_size() < 20_000);
+    assert_eq!(hashlife.to_universe(), naive(start, &rule, 300));
+
+}
@@ -0,0 +1,225 @@
+
+use std::fmt;
+use std::fs;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+use crate::cli::{CliError, Options, DEFAULT_GENERATIONS};
+use crate::cycle::{Cycle, CycleDetector};
+use crate::game::Simulation;
+use crate::pattern::{Format, Pattern, PatternError};
+use crate::random::Random;
+use crate::soup::Soup;
+use crate::universe::BoundingBox;
+
+// Exit code of a run that had to stop at the generation limit before becoming still or periodic
+pub const EXIT_NOT_STABLE: i32 = 3;
+
+// Outcome of a headless run
+#[derive(Debug, Clone)]
+pub struct Summary {
+    pub generation: u64,
+    pub population: usize,
+    pub bounding_box: Option<BoundingBox>,
+    // cycle the universe entered, when looking for one
+    pub cycle: Option<Cycle>,
+    pub until_stable: bool,
+    // soup the run started from, so it can be made again
+    pub soup: Option<Soup>,
+    pub rule: String,
+    pub topology: &'static str,
+    pub elapsed: Duration,
+    pub output: Option<PathBuf>
+}
+
+impl Summary {
+
+    pub fn exit_code(&self) -> i32 {
+
+        if self.until_stable && self.cycle.is_none() {
+            EXIT_NOT_STABLE
+        } else {
+            0
+        }
+
+    }
+
+}
+
+impl fmt::Display for Summary {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+
+        writeln!(f, "generation   {}", self.generation)?;
+        writeln!(f, "population   {}", self.population)?;
+
+        match &self.bounding_box {
+            Some(region) => writeln!(f, "bounds       ({}, {}) to ({}, {})", region.min.0, region.min.1, region.max.0, region.max.1)?,
+            None => writeln!(f, "bounds       empty")?
+        }
+
+        if self.until_stable {
+            match self.cycle {
+                Some(cycle) => writeln!(f, "cycle        {}", cycle)?,
+                None => writeln!(f, "cycle        none")?
+            }
+        }
+
+        if let Some(soup) = &self.soup {
+            writeln!(f, "soup         {}", soup)?;
+        }
+
+        writeln!(f, "rule         {}", self.rule)?;
+        writeln!(f, "topology     {}", self.topology)?;
+        writeln!(f, "elapsed      {:.3} s", self.elapsed.as_secs_f64())?;
+
+        if let Some(output) = &self.output {
+            writeln!(f, "output       {}", output.display())?;
+        }
+
+        Ok(())
+
+    }
+}
+
+// Runs the simulation described by the options without a terminal interface, then writes the
+// final pattern if an output file was given
+pub fn run(options: &Options) -> Result<Summary, CliError> {
+
+    // a soup without a seed is reported with the one picked
+    let mut options = options.clone();
+    options.seed.get_or_insert_with(Random::seed_from_time);
+
+    let mut game = options.build()?;
+    let generations = options.generations.unwrap_or(DEFAULT_GENERATIONS);
+    let start = Instant::now();
+
+    let cycle = if options.until_stable {
+        run_until_stable(game.as_mut(), generations)?
+    } else {
+        game.advance(generations).map_err(|_| CliError::SteppingFailed { generation: game.get_evolution() })?;
+        None
+    };
+
+    if let Some(path) = &options.output {
+        let format = options.format.or_else(|| Format::from_extension(path)).unwrap_or(Format::Rle);
+        fs::write(path, Pattern::from_simulation(game.as_ref()).write(format)?).map_err(PatternError::from)?;
+    }
+
+    Ok(Summary {
+        generation: game.get_evolution(),
+        population: game.population(),
+        bounding_box: game.bounding_box(),
+        cycle,
+        soup: options.soup(),
+        until_stable: options.until_stable,
+        rule: game.get_rule().to_string(),
+        topology: game.topology_name(),
+        elapsed: start.elapsed(),
+        output: options.output.clone()
+    })
+
+}
+
+// Steps one generation at a time until the universe repeats itself, returning the cycle, or None
+// when the limit came first
+fn run_until_stable(game: &mut dyn Simulation, limit: u64) -> Result<Option<Cycle>, CliError> {
+
+    let mut detector = CycleDetector::new();
+
+    for _ in 0..limit {
+        if let Some(cycle) = detector.observe(game) {
+            return Ok(Some(cycle));
+        }
+        game.update().map_err(|_| CliError::SteppingFailed { generation: game.get_evolution() })?;
+    }
+
+    Ok(detector.observe(game))
+
+}
+
+
+// tests
+#[cfg(test)]
+use crate::cli;
+
+#[cfg(test)]
+fn batch(args: &[&str]) -> Options {
+    match cli::parse(args.iter().map(|arg| arg.to_string())) {
+        Ok(cli::Command::Run(options)) => *options,
+        _ => panic!("invalid options")
+    }
+}
+
+#[test]
+fn test_headless_run() {
+
+    let path = std::env::temp_dir().join(format!("game_of_life_headless_{}.cells", std::process::id()));
+    let output = path.to_str().unwrap();
+
+    // the glider gun keeps firing, so it never becomes stable
+    let summary = run(&batch(&["--headless", "--generations", "30", "--until-stable", "--output", output])).unwrap();
+    assert_eq!(summary.generation, 30);
+    assert_eq!(summary.cycle, None);
+    assert_eq!(summary.exit_code(), EXIT_NOT_STABLE);
+    assert!(summary.to_string().contains("cycle        none\n"));
+
+    let saved = Pattern::load(&path).unwrap();
+    assert_eq!(saved.population(), summary.population);
+    fs::remove_file(&path).unwrap();
+
+    // a fixed number of generations on the unbounded engine
+    let summary = run(&batch(&["--headless", "--topology", "unbounded", "--generations", "60"])).unwrap();
+    assert_eq!(summary.generation, 60);
+    assert_eq!(summary.exit_code(), 0);
+    assert!(!summary.to_string().contains("cycle"));
+
+    // soups report their seed, the same seed gives the same run
+    let soup = ["--headless", "--width", "32", "--height", "32", "--soup-size", "16x16", "--symmetry", "C2", "--seed", "4",
+        "--generations", "20"];
+    let summary = run(&batch(&soup)).unwrap();
+    assert!(summary.to_string().contains("soup         seed 4, density 0.50, C2\n"));
+    assert_eq!(run(&batch(&soup)).unwrap().population, summary.population);
+
+}
+
+#[test]
+fn test_headless_becomes_stable() {
+
+    let directory = std::env::temp_dir().join(format!("game_of_life_stable_{}", std::process::id()));
+    fs::create_dir_all(&directory).unwrap();
+    let input = directory.join("pre-block.rle");
+    let output = directory.join("block.lif");
+
+    // three cells of a block fill in after one generation, then nothing changes
+    fs::write(&input, "x = 2, y = 2\n2o$o!\n").unwrap();
+    let summary = run(&batch(&["--headless", "--pattern", input.to_str().unwrap(), "--until-stable",
+        "--output", output.to_str().unwrap()])).unwrap();
+
+    assert_eq!(summary.cycle, Some(Cycle { start: 1, period: 1 }));
+    assert_eq!(summary.generation, 2);
+    assert!(summary.to_string().contains("cycle        still from generation 1\n"));
+    assert_eq!(summary.population, 4);
+    assert_eq!(summary.exit_code(), 0);
+    assert_eq!(Pattern::load(&output).unwrap().cells, vec![(0, 0), (1, 0), (0, 1), (1, 1)]);
+
+    // a blinker repeats itself from the start
+    fs::write(&input, "x = 3, y = 1\n3o!\n").unwrap();
+    let summary = run(&batch(&["--headless", "--pattern", input.to_str().unwrap(), "--until-stable"])).unwrap();
+    assert_eq!(summary.cycle, Some(Cycle { start: 0, period: 2 }));
+    assert!(summary.to_string().contains("cycle        period 2 from generation 0\n"));
+
+    fs::remove_dir_all(&directory).unwrap();
+
+}
+
+#[test]
+fn test_headless_stepping_fails() {
+
+    // a glider flies further than the HashLife tree can reach
+    let error = run(&batch(&["--headless", "--pattern", "glider", "--topology", "unbounded", "--engine", "hashlife",
+        "--generations", "18446744073709551615"])).unwrap_err();
+
+    assert!(matches!(error, CliError::SteppingFailed { .. }));
+    assert!(error.to_string().starts_with("Could not step past generation "));
+
+}
@@ -0,0 +1,124 @@
+
+use std::collections::VecDeque;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+// Most generations kept, older ones are dropped first
+pub const DEFAULT_CAPACITY: usize = 1 << 20;
+
+// Population of a simulation generation by generation. Engines that skip ahead record only the
+// generations they stop at
+#[derive(Debug, Clone)]
+pub struct PopulationHistory {
+    entries: VecDeque<(u64, usize)>,
+    capacity: usize
+}
+
+impl Default for PopulationHistory {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PopulationHistory {
+
+    pub fn new() -> Self {
+        Self::with_capacity(DEFAULT_CAPACITY)
+    }
+
+    pub fn with_capacity(capacity: usize) -> Self {
+
+        Self {
+            entries: VecDeque::new(),
+            capacity: capacity.max(1)
+        }
+
+    }
+
+    // Records the population of a generation. Whatever was recorded for it or for later
+    // generations is replaced, which happens after rewinding
+    pub fn record(&mut self, generation: u64, population: usize) {
+
+        while self.entries.back().is_some_and(|last| last.0 >= generation) {
+            self.entries.pop_back();
+        }
+
+        if self.entries.len() == self.capacity {
+            self.entries.pop_front();
+        }
+
+        self.entries.push_back((generation, population));
+
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    // (generation, population) pairs, oldest first
+    pub fn entries(&self) -> impl Iterator<Item = &(u64, usize)> {
+        self.entries.iter()
+    }
+
+    // Populations of the latest generations, oldest first
+    pub fn recent(&self, count: usize) -> Vec<u64> {
+
+        let skip = self.entries.len().saturating_sub(count);
+        self.entries.iter().skip(skip).map(|(_, population)| *population as u64).collect()
+
+    }
+
+    pub fn to_csv(&self) -> String {
+
+        let mut contents = String::from("generation,population\n");
+        for (generation, population) in self.entries.iter() {
+            contents.push_str(&format!("{},{}\n", generation, population));
+        }
+
+        contents
+
+    }
+
+    pub fn save_csv(&self, path: &Path) -> io::Result<()> {
+        fs::write(path, self.to_csv())
+    }
+
+}
+
+
+// tests
+#[cfg(test)]
+
+#[test]
+fn test_population_history() {
+
+    let mut history = PopulationHistory::with_capacity(3);
+    assert!(history.is_empty());
+
+    history.record(0, 5);
+    history.record(1, 6);
+    history.record(1, 7);
+    assert_eq!(history.to_csv(), "generation,population\n0,5\n1,7\n");
+
+    // the oldest generations make room for new ones
+    history.record(2, 8);
+    history.record(4, 9);
+    assert_eq!(history.len(), 3);
+    assert_eq!(history.entries().next(), Some(&(1, 7)));
+    assert_eq!(history.recent(2), vec![8, 9]);
+    assert_eq!(history.recent(10), vec![7, 8, 9]);
+
+    // going back in time forgets the generations that follow
+    history.record(2, 10);
+    assert_eq!(history.to_csv(), "generation,population\n1,7\n2,10\n");
+
+}
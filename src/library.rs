@@ -0,0 +1,258 @@
+
+use std::fmt;
+
+use crate::pattern::{Format, Pattern, PatternError};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Category {
+    Spaceship,
+    Oscillator,
+    Methuselah,
+    Gun,
+    Puffer,
+    Growth
+}
+
+impl fmt::Display for Category {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+
+        let name = match self {
+            Category::Spaceship => "spaceship",
+            Category::Oscillator => "oscillator",
+            Category::Methuselah => "methuselah",
+            Category::Gun => "gun",
+            Category::Puffer => "puffer",
+            Category::Growth => "infinite growth"
+        };
+
+        write!(f, "{}", name)
+
+    }
+}
+
+// A classic pattern bundled with the program, kept as RLE in the patterns directory
+#[derive(Debug, Clone, Copy)]
+pub struct LibraryPattern {
+    pub key: &'static str,
+    pub name: &'static str,
+    pub category: Category,
+    pub rle: &'static str
+}
+
+impl LibraryPattern {
+
+    pub fn pattern(&self) -> Result<Pattern, PatternError> {
+        Pattern::parse(self.rle, Format::Rle)
+    }
+
+}
+
+pub const PATTERNS: [LibraryPattern; 14] = [
+    LibraryPattern {
+        key: "glider",
+        name: "Glider",
+        category: Category::Spaceship,
+        rle: include_str!("../patterns/glider.rle")
+    },
+    LibraryPattern {
+        key: "lwss",
+        name: "Lightweight spaceship",
+        category: Category::Spaceship,
+        rle: include_str!("../patterns/lwss.rle")
+    },
+    LibraryPattern {
+        key: "mwss",
+        name: "Middleweight spaceship",
+        category: Category::Spaceship,
+        rle: include_str!("../patterns/mwss.rle")
+    },
+    LibraryPattern {
+        key: "hwss",
+        name: "Heavyweight spaceship",
+        category: Category::Spaceship,
+        rle: include_str!("../patterns/hwss.rle")
+    },
+    LibraryPattern {
+        key: "pulsar",
+        name: "Pulsar",
+        category: Category::Oscillator,
+        rle: include_str!("../patterns/pulsar.rle")
+    },
+    LibraryPattern {
+        key: "pentadecathlon",
+        name: "Pentadecathlon",
+        category: Category::Oscillator,
+        rle: include_str!("../patterns/pentadecathlon.rle")
+    },
+    LibraryPattern {
+        key: "r-pentomino",
+        name: "R-pentomino",
+        category: Category::Methuselah,
+        rle: include_str!("../patterns/r-pentomino.rle")
+    },
+    LibraryPattern {
+        key: "acorn",
+        name: "Acorn",
+        category: Category::Methuselah,
+        rle: include_str!("../patterns/acorn.rle")
+    },
+    LibraryPattern {
+        key: "diehard",
+        name: "Diehard",
+        category: Category::Methuselah,
+        rle: include_str!("../patterns/diehard.rle")
+    },
+    LibraryPattern {
+        key: "gosper-glider-gun",
+        name: "Gosper glider gun",
+        category: Category::Gun,
+        rle: include_str!("../patterns/gosper-glider-gun.rle")
+    },
+    LibraryPattern {
+        key: "simkin-glider-gun",
+        name: "Simkin glider gun",
+        category: Category::Gun,
+        rle: include_str!("../patterns/simkin-glider-gun.rle")
+    },
+    LibraryPattern {
+        key: "block-laying-switch-engine",
+        name: "Block-laying switch engine",
+        category: Category::Puffer,
+        rle: include_str!("../patterns/block-laying-switch-engine.rle")
+    },
+    LibraryPattern {
+        key: "infinite-growth-5x5",
+        name: "5x5 infinite growth",
+        category: Category::Growth,
+        rle: include_str!("../patterns/infinite-growth-5x5.rle")
+    },
+    LibraryPattern {
+        key: "infinite-growth-one-cell-high",
+        name: "One cell high infinite growth",
+        category: Category::Growth,
+        rle: include_str!("../patterns/infinite-growth-one-cell-high.rle")
+    }
+];
+
+// Looks a bundled pattern up by its key, ignoring case
+pub fn find(key: &str) -> Option<&'static LibraryPattern> {
+    PATTERNS.iter().find(|entry| entry.key.eq_ignore_ascii_case(key))
+}
+
+
+// tests
+#[cfg(test)]
+use crate::{cell, rule, universe::Universe};
+
+#[cfg(test)]
+fn universe_of(entry: &LibraryPattern) -> Universe {
+
+    let mut universe = Universe::new();
+    for (x, y) in entry.pattern().unwrap().cells {
+        universe.set_state(&(x as i64, y as i64), cell::CellState::Alive);
+    }
+
+    universe
+
+}
+
+#[cfg(test)]
+fn evolve(universe: &Universe, generations: usize) -> Universe {
+
+    let rule = rule::Rule::default();
+    let mut universe = universe.step(&rule);
+    for _ in 1..generations {
+        universe = universe.step(&rule);
+    }
+
+    universe
+
+}
+
+// Living cells moved so that the top left corner of their bounding box is at the origin
+#[cfg(test)]
+fn shape(universe: &Universe) -> Vec<(i64, i64)> {
+
+    let mut cells = universe.live_cells();
+    let min_x = cells.iter().map(|(x, _)| *x).min().unwrap_or(0);
+    let min_y = cells.iter().map(|(_, y)| *y).min().unwrap_or(0);
+
+    cells.iter_mut().for_each(|(x, y)| { *x -= min_x; *y -= min_y; });
+    cells.sort_unstable();
+    cells
+
+}
+
+#[test]
+fn test_library_parses() {
+
+    for entry in PATTERNS.iter() {
+        let pattern = entry.pattern().unwrap();
+        assert!(pattern.population() > 0, "{} is empty", entry.key);
+        assert_eq!(pattern.name.as_deref(), Some(entry.name), "{} has another name", entry.key);
+    }
+
+    assert_eq!(find("GLIDER").map(|entry| entry.name), Some("Glider"));
+
+}
+
+#[test]
+fn test_library_spaceships_and_oscillators() {
+
+    let periods = [("glider", 4), ("lwss", 4), ("mwss", 4), ("hwss", 4), ("pulsar", 3), ("pentadecathlon", 15)];
+
+    for (key, period) in periods.iter() {
+
+        let entry = find(key).unwrap();
+        let start = universe_of(entry);
+        let end = evolve(&start, *period);
+
+        assert_eq!(shape(&end), shape(&start), "{} does not come back after {}", key, period);
+
+        // spaceships move, oscillators stay in place
+        let moved = end.bounding_box() != start.bounding_box();
+        assert_eq!(moved, entry.category == Category::Spaceship, "{}", key);
+
+    }
+
+}
+
+#[test]
+fn test_library_methuselahs() {
+
+    let diehard = evolve(&universe_of(find("diehard").unwrap()), 130);
+    assert_eq!(diehard.population(), 0);
+
+    // still busy long after they started
+    for key in ["r-pentomino", "acorn"].iter() {
+        let universe = evolve(&universe_of(find(key).unwrap()), 500);
+        assert!(universe.population() > 50, "{} died out early", key);
+    }
+
+}
+
+#[test]
+fn test_library_guns_and_puffers() {
+
+    // a gun adds a glider of five cells every period
+    for (key, period) in [("gosper-glider-gun", 30), ("simkin-glider-gun", 120)].iter() {
+        let warm = evolve(&universe_of(find(key).unwrap()), period * 2);
+        let later = evolve(&warm, *period);
+        assert_eq!(later.population(), warm.population() + 5, "{} does not fire", key);
+    }
+
+    // a puffer moves on and leaves the same debris every period, eight blocks for the switch engine
+    let warm = evolve(&universe_of(find("block-laying-switch-engine").unwrap()), 1152);
+    let once = evolve(&warm, 288);
+    let twice = evolve(&once, 288);
+    assert_eq!(once.population(), warm.population() + 32);
+    assert_eq!(twice.population(), once.population() + 32);
+    assert_ne!(once.bounding_box(), warm.bounding_box());
+
+    for entry in PATTERNS.iter().filter(|entry| entry.category == Category::Growth) {
+        let warm = evolve(&universe_of(entry), 1000);
+        let later = evolve(&warm, 600);
+        assert!(later.population() > warm.population() + 20, "{} does not grow", entry.key);
+    }
+
+}
@@ -0,0 +1,203 @@
+
+use crate::pattern::{Pattern, PatternError};
+use crate::rule;
+
+pub const LIFE_105_HEADER: &str = "#Life 1.05";
+pub const LIFE_106_HEADER: &str = "#Life 1.06";
+
+// The format has no line for the name, so it is kept in a description starting with this
+const NAME_PREFIX: &str = "Name:";
+
+// Reads a pattern in the Life 1.05 format. Cells come in blocks, each starting with a `#P x y`
+// line giving the position of its top left corner, with `*` for living cells and `.` for dead
+// ones. `#D` lines are descriptions, the first one starting with `Name:` naming the pattern,
+// `#N` selects Conway's rule and `#R` gives a rule in S/B notation
+pub fn parse_105(contents: &str) -> Result<Pattern, PatternError> {
+
+    let mut pattern = Pattern::default();
+    let mut cells: Vec<(i64, i64)> = Vec::new();
+    let mut block: (i64, i64) = (0, 0);
+    let mut block_line = "";
+    let mut row = 0;
+
+    for (index, line) in contents.lines().enumerate() {
+
+        let line = line.trim();
+        let line_number = index + 1;
+
+        if line.is_empty() || line.starts_with(LIFE_105_HEADER) {
+            continue;
+        }
+
+        if let Some(description) = line.strip_prefix("#D") {
+            match description.trim().strip_prefix(NAME_PREFIX) {
+                Some(name) if pattern.name.is_none() => pattern.name = Some(name.trim().to_string()),
+                _ => pattern.comments.push(description.trim().to_string())
+            }
+        } else if line.starts_with("#N") {
+            pattern.rule = Some(rule::Rule::default());
+        } else if let Some(rulestring) = line.strip_prefix("#R") {
+            pattern.rule = Some(rulestring.trim().parse()?);
+        } else if let Some(position) = line.strip_prefix("#P") {
+            block = parse_coordinates(position, line)?;
+            block_line = line;
+            row = 0;
+        } else if line.starts_with('#') {
+            continue;
+        } else {
+
+            // cells far enough from a block's corner would leave the coordinate range
+            let position = |x: usize| block.0.checked_add(x as i64).zip(block.1.checked_add(row))
+                .ok_or_else(|| PatternError::InvalidCoordinates(block_line.to_string()));
+
+            for (x, character) in line.chars().enumerate() {
+                match character {
+                    '*' | 'O' => cells.push(position(x)?),
+                    '.' => (),
+                    character => return Err(PatternError::UnexpectedCharacter { character, line: line_number })
+                }
+            }
+
+            row += 1;
+
+        }
+
+    }
+
+    pattern.cells = shift_to_origin(&cells)?;
+    pattern.normalize();
+    Ok(pattern)
+
+}
+
+// Reads a pattern in the Life 1.06 format, one `x y` pair per living cell
+pub fn parse_106(contents: &str) -> Result<Pattern, PatternError> {
+
+    let mut cells: Vec<(i64, i64)> = Vec::new();
+
+    for line in contents.lines() {
+
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        cells.push(parse_coordinates(line, line)?);
+
+    }
+
+    Ok(Pattern::from_cells(shift_to_origin(&cells)?))
+
+}
+
+// Writes a pattern in the Life 1.05 format as a single block
+pub fn write_105(pattern: &Pattern) -> String {
+
+    let mut contents = format!("{}\n", LIFE_105_HEADER);
+
+    if let Some(name) = &pattern.name {
+        contents.push_str(&format!("#D {} {}\n", NAME_PREFIX, name));
+    }
+
+    for comment in pattern.comments.iter() {
+        contents.push_str(&format!("#D {}\n", comment));
+    }
+
+    match &pattern.rule {
+        Some(rule) if *rule != rule::Rule::default() => {
+            contents.push_str(&format!("#R {}\n", rule.to_survival_birth()));
+        },
+        _ => contents.push_str("#N\n")
+    }
+
+    contents.push_str("#P 0 0\n");
+
+    contents.push_str(&pattern.rows('*'));
+    contents
+
+}
+
+// Writes a pattern in the Life 1.06 format
+pub fn write_106(pattern: &Pattern) -> String {
+
+    let mut contents = format!("{}\n", LIFE_106_HEADER);
+    for (x, y) in pattern.cells.iter() {
+        contents.push_str(&format!("{} {}\n", x, y));
+    }
+
+    contents
+
+}
+
+fn parse_coordinates(text: &str, line: &str) -> Result<(i64, i64), PatternError> {
+
+    let mut numbers = text.split_whitespace().map(|number| number.parse::<i64>());
+    match (numbers.next(), numbers.next(), numbers.next()) {
+        (Some(Ok(x)), Some(Ok(y)), None) => Ok((x, y)),
+        _ => Err(PatternError::InvalidCoordinates(line.to_string()))
+    }
+
+}
+
+// these formats allow negative coordinates, patterns always start at (0, 0). Cells spread wider
+// than the coordinate range are rejected
+fn shift_to_origin(cells: &[(i64, i64)]) -> Result<Vec<(usize, usize)>, PatternError> {
+
+    let min_x = cells.iter().map(|(x, _)| *x).min().unwrap_or(0);
+    let min_y = cells.iter().map(|(_, y)| *y).min().unwrap_or(0);
+
+    cells.iter()
+        .map(|(x, y)| match (x.checked_sub(min_x), y.checked_sub(min_y)) {
+            (Some(x), Some(y)) => Ok((x as usize, y as usize)),
+            _ => Err(PatternError::InvalidCoordinates(format!("{} {}", x, y)))
+        })
+        .collect()
+
+}
+
+
+// tests
+#[cfg(test)]
+
+#[test]
+fn test_life_105_round_trip() {
+
+    let contents = "#Life 1.05\n#D Glider\n#R 23/36\n#P -1 -1\n.*\n..*\n***\n#P 10 -1\n**\n#P 0 3\n*\n";
+
+    let pattern = parse_105(contents).unwrap();
+    assert_eq!(pattern.comments, vec!["Glider".to_string()]);
+    assert_eq!(pattern.rule, Some("B36/S23".parse().unwrap()));
+    assert_eq!(pattern.size, (13, 5));
+    assert_eq!(pattern.cells, vec![(1, 0), (11, 0), (12, 0), (2, 1), (0, 2), (1, 2), (2, 2), (1, 4)]);
+
+    let written = write_105(&pattern);
+    assert!(written.contains("#R 23/36\n"));
+    assert_eq!(parse_105(&written).unwrap(), pattern);
+
+    // the name is kept in a description of its own
+    let named = Pattern { name: Some("Glider".to_string()), ..pattern };
+    let written = write_105(&named);
+    assert!(written.contains("#D Name: Glider\n#D Glider\n"));
+    assert_eq!(parse_105(&written).unwrap(), named);
+
+    assert!(matches!(parse_105("#P 1\n*"), Err(PatternError::InvalidCoordinates(_))));
+    assert!(matches!(parse_105("#Life 1.05\n#P 9223372036854775807 0\n**\n"), Err(PatternError::InvalidCoordinates(_))));
+
+}
+
+#[test]
+fn test_life_106_round_trip() {
+
+    let contents = "#Life 1.06\n0 -1\n1 0\n-1 1\n0 1\n1 1\n";
+
+    let pattern = parse_106(contents).unwrap();
+    assert_eq!(pattern.size, (3, 3));
+    assert_eq!(pattern.cells, vec![(1, 0), (2, 1), (0, 2), (1, 2), (2, 2)]);
+
+    assert_eq!(parse_106(&write_106(&pattern)).unwrap(), pattern);
+
+    assert!(matches!(parse_106("#Life 1.06\n1 x\n"), Err(PatternError::InvalidCoordinates(_))));
+    assert!(matches!(parse_106("#Life 1.06\n9223372036854775807 0\n-9223372036854775808 0\n"),
+        Err(PatternError::InvalidCoordinates(_))));
+
+}
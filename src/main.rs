@@ -1,13 +1,12 @@
-#[allow(dead_code)]
-
-use std::{io, time::Duration};
-use termion::{event::Key, input::MouseTerminal, raw::IntoRawMode, screen::AlternateScreen};
+use std::{env, io, process};
+use termion::{input::MouseTerminal, raw::IntoRawMode, screen::AlternateScreen};
 use tui::{
     backend::TermionBackend,
     layout::{Constraint, Direction, Layout, Rect},
+    style::{Color, Modifier, Style},
     widgets::{
         canvas::Canvas,
-        Block, Borders,
+        Block, Borders, Clear, List, ListItem, ListState, Paragraph, Sparkline,
     },
     Terminal,
 };
@@ -17,41 +16,102 @@ pub mod grid;
 pub mod cell;
 pub mod game;
 pub mod events;
+pub mod rule;
+pub mod topology;
+pub mod universe;
+pub mod hashlife;
+pub mod pattern;
+pub mod rle;
+pub mod plaintext;
+pub mod lif;
+pub mod random;
+pub mod soup;
+pub mod history;
+pub mod cycle;
+pub mod census;
+pub mod library;
+pub mod timeline;
+pub mod cli;
+pub mod viewport;
+pub mod app;
+pub mod headless;
+pub mod search;
 
 use crate::events::{Config, Event, Events};
-use game::GameOfLife;
+use app::App;
+use viewport::Preview;
+use random::Random;
+use soup::Soup;
 
+// Width of the statistics panel beside the canvas, in terminal cells
+const STATS_WIDTH: u16 = 28;
 
-struct App {
-    area: Rect,
-    game: GameOfLife
-}
+// Size of the pattern library popup and of the list of names in it, in terminal cells
+const PICKER_SIZE: (u16, u16) = (76, 22);
+const PICKER_LIST_WIDTH: u16 = 34;
 
-impl App {
+// Rectangle of at most the given size in the middle of an area
+fn centered(area: Rect, size: (u16, u16)) -> Rect {
 
-    fn new() -> Self {
+    let width = size.0.min(area.width);
+    let height = size.1.min(area.height);
+    Rect::new(area.x + (area.width - width) / 2, area.y + (area.height - height) / 2, width, height)
 
-        Self {
-            area: Rect::new(0, 0, 200, 200), // x, y, width, height layout
-            game: GameOfLife::default()
-        }
+}
 
-    }
 
-    fn update(&mut self) {
+fn main() -> Result<(), Box<dyn std::error::Error>> {
 
-        match self.game.update() {
-            Ok(_) => (),
-            Err(_) => println!("Out of bounds error!")
+    // Read the options and build the simulation before taking over the terminal, so errors
+    // and help are printed normally
+    let mut options = match cli::parse(env::args().skip(1)) {
+        Ok(cli::Command::Run(options)) => *options,
+        Ok(cli::Command::Help) => {
+            print!("{}", cli::USAGE);
+            return Ok(());
+        },
+        Err(error) => {
+            eprintln!("error: {}", error);
+            process::exit(2);
         }
+    };
 
-    }
-
-}
+    // the seed is picked once, so soups can be made again and reseeding carries on from it
+    options.seed.get_or_insert_with(Random::seed_from_time);
 
+    if options.search.is_some() {
+        match search::run(&options) {
+            Ok(report) => {
+                print!("{}", report);
+                process::exit(0);
+            },
+            Err(error) => {
+                eprintln!("error: {}", error);
+                process::exit(1);
+            }
+        }
+    }
 
+    if options.headless {
+        match headless::run(&options) {
+            Ok(summary) => {
+                print!("{}", summary);
+                process::exit(summary.exit_code());
+            },
+            Err(error) => {
+                eprintln!("error: {}", error);
+                process::exit(1);
+            }
+        }
+    }
 
-fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let game = match options.build() {
+        Ok(game) => game,
+        Err(error) => {
+            eprintln!("error: {}", error);
+            process::exit(1);
+        }
+    };
 
     let stdout = io::stdout().into_raw_mode()?;
     let stdout = MouseTerminal::from(stdout);
@@ -59,44 +119,114 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     let backend = TermionBackend::new(stdout);
     let mut terminal = Terminal::new(backend)?;
 
-    // Setup event handlers
+    // App
+    let mut app = App::new(game)
+        .with_tick_rate(options.tick_rate)
+        .with_history_budget(options.history_budget)
+        .with_soup(options.soup().unwrap_or_else(|| Soup::new(options.seed.unwrap_or(0))), options.soup_size());
+
+    // Setup event handlers, ticking at the rate the app settled on
     let config = Config {
-        tick_rate: Duration::from_millis(250),
+        tick_rate: app.tick_rate(),
         ..Default::default()
     };
-    let events = Events::with_config(config);
+    let mut events = Events::with_config(config);
 
-    // App
-    let mut app = App::new();
+    // the app decides when to quit, since keys such as q mean something else in the library
+    // picker and input has to keep coming after them
+    events.disable_exit_key();
 
     loop {
 
         terminal.draw(|f| {
+            let rows = Layout::default()
+                .direction(Direction::Vertical)
+                .constraints([Constraint::Min(3), Constraint::Length(1)].as_ref())
+                .split(f.size());
             let chunks = Layout::default()
                 .direction(Direction::Horizontal)
-                .constraints([Constraint::Percentage(100)].as_ref())
-                .split(f.size());
+                .constraints([Constraint::Min(3), Constraint::Length(STATS_WIDTH)].as_ref())
+                .split(rows[0]);
+            app.resize(chunks[0]);
             let canvas = Canvas::default()
-                .block(Block::default().borders(Borders::ALL).title("Game of Life"))
+                .block(Block::default().borders(Borders::ALL).title(app.title()))
                 .paint(|ctx| {
-                    ctx.draw(&app.game.cell_grid);
+                    ctx.draw(&app.view());
+                    ctx.layer();
+                    if let Some(outline) = app.selection_outline() {
+                        ctx.draw(&outline);
+                    }
+                    ctx.draw(&app.cursor_marker());
                 });
             f.render_widget(canvas, chunks[0]);
+            let panel = Layout::default()
+                .direction(Direction::Vertical)
+                .constraints([Constraint::Length(11), Constraint::Min(3)].as_ref())
+                .split(chunks[1]);
+            let stats = Paragraph::new(app.stats())
+                .block(Block::default().borders(Borders::ALL).title("Stats"));
+            f.render_widget(stats, panel[0]);
+            match app.census() {
+                Some(census) => {
+                    let census = Paragraph::new(census)
+                        .block(Block::default().borders(Borders::ALL).title("Census"));
+                    f.render_widget(census, panel[1]);
+                },
+                None => {
+                    let population = app.population_chart(panel[1].width.saturating_sub(2) as usize);
+                    let sparkline = Sparkline::default()
+                        .block(Block::default().borders(Borders::ALL).title("Population"))
+                        .data(&population)
+                        .style(Style::default().fg(Color::Cyan));
+                    f.render_widget(sparkline, panel[1]);
+                }
+            }
+            f.render_widget(Paragraph::new(app.status()), rows[1]);
+            if let Some(index) = app.picker() {
+                let popup = centered(f.size(), PICKER_SIZE);
+                let halves = Layout::default()
+                    .direction(Direction::Horizontal)
+                    .constraints([Constraint::Length(PICKER_LIST_WIDTH), Constraint::Min(3)].as_ref())
+                    .split(popup);
+                let names: Vec<ListItem> = library::PATTERNS.iter()
+                    .map(|entry| ListItem::new(entry.name))
+                    .collect();
+                let list = List::new(names)
+                    .block(Block::default().borders(Borders::ALL).title("Patterns"))
+                    .highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+                let mut state = ListState::default();
+                state.select(Some(index));
+                f.render_widget(Clear, popup);
+                f.render_stateful_widget(list, halves[0], &mut state);
+                let entry = &library::PATTERNS[index];
+                let pattern = entry.pattern().unwrap_or_default();
+                let title = format!(
+                    "{}, {}x{}, {} cells", entry.category, pattern.size.0, pattern.size.1, pattern.population()
+                );
+                let size = (
+                    halves[1].width.saturating_sub(2) as usize * 2,
+                    halves[1].height.saturating_sub(2) as usize * 4
+                );
+                let preview = Canvas::default()
+                    .block(Block::default().borders(Borders::ALL).title(title))
+                    .paint(|ctx| ctx.draw(&Preview { pattern: &pattern, size, color: Color::White }));
+                f.render_widget(preview, halves[1]);
+            }
         })?;
 
         match events.next()? {
-            Event::Input(input) => match input {
-                Key::Char('q') => {
-                    break;
+            Event::Input(input) => {
+                app.on_key(input);
+                if events.tick_rate() != app.tick_rate() {
+                    events.set_tick_rate(app.tick_rate());
                 }
-
-                // all other key presses
-                _ => {}
             },
+            Event::Mouse(mouse) => app.on_mouse(mouse),
+            Event::Tick => app.on_tick()
+        }
 
-            Event::Tick => {
-                app.update();
-            }
+        if app.should_quit() {
+            break;
         }
 
     }
@@ -0,0 +1,352 @@
+
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use crate::cell;
+use crate::game::Simulation;
+use crate::grid;
+use crate::lif;
+use crate::plaintext;
+use crate::rle;
+use crate::rule;
+
+#[derive(Debug)]
+pub enum PatternError {
+    Io(io::Error),
+    InvalidHeader(String),
+    InvalidRule(rule::RuleParseError),
+    InvalidCoordinates(String),
+    UnexpectedCharacter { character: char, line: usize },
+    RunTooLong { line: usize },
+    TooLargeToWrite,
+    UnknownFormat(String),
+    DoesNotFit
+}
+
+impl fmt::Display for PatternError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PatternError::Io(error) => write!(f, "Could not access pattern file: {}", error),
+            PatternError::InvalidHeader(header) => write!(f, "Invalid pattern header '{}'", header),
+            PatternError::InvalidRule(error) => write!(f, "Invalid pattern rule: {}", error),
+            PatternError::InvalidCoordinates(line) => write!(f, "Invalid cell coordinates '{}'", line),
+            PatternError::UnexpectedCharacter { character, line } => {
+                write!(f, "Unexpected character '{}' on line {}", character, line)
+            },
+            PatternError::RunTooLong { line } => write!(f, "Run count too large on line {}", line),
+            PatternError::TooLargeToWrite => {
+                write!(f, "Pattern is too large for the cells and Life 1.05 formats, save it as rle or life106")
+            },
+            PatternError::UnknownFormat(format) => {
+                write!(f, "Unknown pattern format '{}', expected rle, cells, life105 or life106", format)
+            },
+            PatternError::DoesNotFit => write!(f, "Pattern does not fit on the grid at that offset")
+        }
+    }
+}
+
+impl std::error::Error for PatternError {}
+
+impl From<io::Error> for PatternError {
+    fn from(error: io::Error) -> Self {
+        PatternError::Io(error)
+    }
+}
+
+impl From<rule::RuleParseError> for PatternError {
+    fn from(error: rule::RuleParseError) -> Self {
+        PatternError::InvalidRule(error)
+    }
+}
+
+
+// Plaintext and Life 1.05 files spell out every dead cell before the last living one of each row,
+// patterns with a larger bounding box are only written in the other formats
+pub const MAX_DENSE_AREA: usize = 1 << 24;
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Format {
+    Rle,
+    Plaintext,
+    Life105,
+    Life106
+}
+
+impl Format {
+
+    // Guesses the format from a file extension, .lif files may hold either Life format
+    pub fn from_extension(path: &Path) -> Option<Self> {
+
+        let extension = path.extension()?.to_str()?.to_ascii_lowercase();
+        match extension.as_str() {
+            "rle" => Some(Format::Rle),
+            "cells" => Some(Format::Plaintext),
+            "lif" | "life" => Some(Format::Life106),
+            _ => None
+        }
+
+    }
+
+    // Recognizes a format from the first line that is not blank
+    pub fn detect(contents: &str) -> Self {
+
+        let first_line = contents.lines().map(|line| line.trim()).find(|line| !line.is_empty()).unwrap_or("");
+
+        if first_line.starts_with(lif::LIFE_105_HEADER) {
+            Format::Life105
+        } else if first_line.starts_with(lif::LIFE_106_HEADER) {
+            Format::Life106
+        } else if first_line.starts_with('!') || first_line.chars().all(|c| c == '.' || c == 'O') {
+            Format::Plaintext
+        } else {
+            Format::Rle
+        }
+
+    }
+
+}
+
+impl std::str::FromStr for Format {
+    type Err = PatternError;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name.to_ascii_lowercase().as_str() {
+            "rle" => Ok(Format::Rle),
+            "cells" | "plaintext" => Ok(Format::Plaintext),
+            "life105" | "1.05" => Ok(Format::Life105),
+            "life106" | "1.06" | "lif" => Ok(Format::Life106),
+            _ => Err(PatternError::UnknownFormat(name.to_string()))
+        }
+    }
+}
+
+
+// A pattern independent of any file format. Cells are relative to the top left corner of its
+// bounding rectangle
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Pattern {
+    pub name: Option<String>,
+    pub comments: Vec<String>,
+    pub rule: Option<rule::Rule>,
+    pub size: (usize, usize),
+    pub cells: Vec<(usize, usize)>
+}
+
+impl Pattern {
+
+    pub fn from_cells(cells: Vec<(usize, usize)>) -> Self {
+
+        let mut pattern = Pattern {
+            cells,
+            ..Default::default()
+        };
+
+        pattern.normalize();
+        pattern
+
+    }
+
+    // Captures the living cells of a grid, trimmed to their bounding rectangle
+    pub fn from_grid(cell_grid: &grid::Grid) -> Self {
+
+        let live_cells = cell_grid.live_cells();
+
+        let min_x = live_cells.iter().map(|(x, _)| *x).min().unwrap_or(0);
+        let min_y = live_cells.iter().map(|(_, y)| *y).min().unwrap_or(0);
+
+        Pattern::from_cells(live_cells.iter().map(|(x, y)| (x - min_x, y - min_y)).collect())
+
+    }
+
+    // Captures the living cells of any engine, trimmed to their bounding box, along with its rule
+    pub fn from_simulation(game: &dyn Simulation) -> Self {
+
+        let cells = match game.bounding_box() {
+            Some(region) => game.cells_in(&region).iter()
+                .map(|(x, y)| ((x - region.min.0) as usize, (y - region.min.1) as usize))
+                .collect(),
+            None => Vec::new()
+        };
+
+        let mut pattern = Pattern::from_cells(cells);
+        pattern.rule = Some(*game.get_rule());
+        pattern
+
+    }
+
+    pub fn parse(contents: &str, format: Format) -> Result<Self, PatternError> {
+        match format {
+            Format::Rle => rle::parse(contents),
+            Format::Plaintext => plaintext::parse(contents),
+            Format::Life105 => lif::parse_105(contents),
+            Format::Life106 => lif::parse_106(contents)
+        }
+    }
+
+    pub fn write(&self, format: Format) -> Result<String, PatternError> {
+
+        let dense = format == Format::Plaintext || format == Format::Life105;
+        if dense && self.size.0.checked_mul(self.size.1).is_none_or(|area| area > MAX_DENSE_AREA) {
+            return Err(PatternError::TooLargeToWrite);
+        }
+
+        Ok(match format {
+            Format::Rle => rle::write(self),
+            Format::Plaintext => plaintext::write(self),
+            Format::Life105 => lif::write_105(self),
+            Format::Life106 => lif::write_106(self)
+        })
+
+    }
+
+    // Loads a pattern in any supported format. The contents decide between formats sharing an
+    // extension and are the only hint for files without a known one
+    pub fn load(path: &Path) -> Result<Self, PatternError> {
+
+        let contents = fs::read_to_string(path)?;
+
+        let format = match Format::from_extension(path) {
+            Some(Format::Rle) => Format::Rle,
+            Some(Format::Plaintext) => Format::Plaintext,
+            _ => Format::detect(&contents)
+        };
+
+        Pattern::parse(&contents, format)
+
+    }
+
+    // Saves a pattern in the format matching the file extension, RLE when there is none
+    pub fn save(&self, path: &Path) -> Result<(), PatternError> {
+        let format = Format::from_extension(path).unwrap_or(Format::Rle);
+        fs::write(path, self.write(format)?)?;
+        Ok(())
+    }
+
+    // Rows of the text formats built from the cells in order, one line each with `.` for dead cells.
+    // Dead cells at the end of a row are left out, an empty row keeps a single one so it is not
+    // mistaken for a blank line
+    pub fn rows(&self, alive: char) -> String {
+
+        let mut cells = self.cells.clone();
+        cells.sort_by_key(|(x, y)| (*y, *x));
+        cells.dedup();
+
+        let mut contents = String::new();
+        let mut position = (0, 0);
+
+        for (x, y) in cells.iter() {
+            while position.1 < *y {
+                contents.push_str(if position.0 == 0 { ".\n" } else { "\n" });
+                position = (0, position.1 + 1);
+            }
+            contents.extend(std::iter::repeat_n('.', x - position.0));
+            contents.push(alive);
+            position.0 = x + 1;
+        }
+
+        while position.1 < self.size.1 {
+            contents.push_str(if position.0 == 0 { ".\n" } else { "\n" });
+            position = (0, position.1 + 1);
+        }
+
+        contents
+
+    }
+
+    pub fn population(&self) -> usize {
+        self.cells.len()
+    }
+
+    // Sorts the cells row by row, drops duplicates and grows the size to cover every cell
+    pub fn normalize(&mut self) {
+
+        self.cells.sort_by_key(|(x, y)| (*y, *x));
+        self.cells.dedup();
+
+        for (x, y) in self.cells.iter() {
+            self.size.0 = self.size.0.max(x + 1);
+            self.size.1 = self.size.1.max(y + 1);
+        }
+
+    }
+
+    // Brings the pattern's cells to life on a grid with its top left corner at the offset
+    pub fn place(&self, cell_grid: &mut grid::Grid, offset: (usize, usize)) -> Result<(), PatternError> {
+
+        let grid_size = cell_grid.get_size();
+        let fits = |offset: usize, size: usize, grid_size: usize| offset.checked_add(size).is_some_and(|end| end <= grid_size);
+        if !fits(offset.0, self.size.0, grid_size.0) || !fits(offset.1, self.size.1, grid_size.1) {
+            return Err(PatternError::DoesNotFit);
+        }
+
+        for (x, y) in self.cells.iter() {
+            cell_grid.set_state(&(offset.0 + x, offset.1 + y), cell::CellState::Alive)
+                .map_err(|_| PatternError::DoesNotFit)?;
+        }
+
+        Ok(())
+
+    }
+
+}
+
+
+// tests
+#[cfg(test)]
+
+#[test]
+fn test_pattern_format_detection() {
+
+    assert_eq!(Format::detect("#Life 1.05\n#P 0 0\n*"), Format::Life105);
+    assert_eq!(Format::detect("\n#Life 1.06\n0 0"), Format::Life106);
+    assert_eq!(Format::detect("!Name: Blinker\nOOO"), Format::Plaintext);
+    assert_eq!(Format::detect(".O\nO."), Format::Plaintext);
+    assert_eq!(Format::detect("#N Blinker\nx = 3, y = 1\n3o!"), Format::Rle);
+
+    assert_eq!(Format::from_extension(Path::new("gun.RLE")), Some(Format::Rle));
+    assert_eq!(Format::from_extension(Path::new("glider.cells")), Some(Format::Plaintext));
+    assert_eq!(Format::from_extension(Path::new("glider.txt")), None);
+
+}
+
+#[test]
+fn test_pattern_load_and_save() {
+
+    let glider = Pattern::from_cells(vec![(1, 0), (2, 1), (0, 2), (1, 2), (2, 2)]);
+    let directory = std::env::temp_dir().join(format!("game_of_life_{}", std::process::id()));
+    fs::create_dir_all(&directory).unwrap();
+
+    // every format survives a trip through a file, picked by extension or by contents
+    for (file_name, format) in [("glider.rle", Format::Rle), ("glider.cells", Format::Plaintext),
+        ("glider.lif", Format::Life106), ("glider", Format::Life105)].iter() {
+
+        let path = directory.join(file_name);
+        fs::write(&path, glider.write(*format).unwrap()).unwrap();
+        assert_eq!(Pattern::load(&path).unwrap().cells, glider.cells);
+
+        glider.save(&path).unwrap();
+        assert_eq!(Pattern::load(&path).unwrap().cells, glider.cells);
+
+    }
+
+    fs::remove_dir_all(&directory).unwrap();
+
+}
+
+#[test]
+fn test_pattern_write_far_apart() {
+
+    // rows are built from the cells, not from the whole bounding box
+    let sparse = Pattern::from_cells(vec![(4, 0), (0, 3)]);
+    assert_eq!(sparse.rows('O'), "....O\n.\n.\nO\n");
+
+    // only the formats spelling out dead cells are limited
+    let far = Pattern::from_cells(vec![(0, 0), (3_000_000_000, 0)]);
+    assert!(matches!(far.write(Format::Plaintext), Err(PatternError::TooLargeToWrite)));
+    assert!(matches!(far.write(Format::Life105), Err(PatternError::TooLargeToWrite)));
+    assert_eq!(Pattern::parse(&far.write(Format::Rle).unwrap(), Format::Rle).unwrap().cells, far.cells);
+    assert_eq!(Pattern::parse(&far.write(Format::Life106).unwrap(), Format::Life106).unwrap().cells, far.cells);
+
+}
@@ -0,0 +1,89 @@
+
+use crate::pattern::{Pattern, PatternError};
+
+// Reads a pattern in the plaintext format used by .cells files:
+//
+//   !Name: Glider
+//   .O.
+//   ..O
+//   OOO
+//
+// `O` is a living cell, `.` a dead one and lines starting with `!` are comments
+pub fn parse(contents: &str) -> Result<Pattern, PatternError> {
+
+    let mut pattern = Pattern::default();
+    let mut y = 0;
+
+    for (index, line) in contents.lines().enumerate() {
+
+        let line = line.trim_end();
+
+        if let Some(comment) = line.strip_prefix('!') {
+
+            match comment.strip_prefix("Name:") {
+                Some(name) => pattern.name = Some(name.trim().to_string()),
+                None => pattern.comments.push(comment.trim().to_string())
+            }
+
+            continue;
+
+        }
+
+        for (x, character) in line.chars().enumerate() {
+            match character {
+                'O' | '*' => pattern.cells.push((x, y)),
+                '.' => (),
+                character => return Err(PatternError::UnexpectedCharacter { character, line: index + 1 })
+            }
+        }
+
+        pattern.size.0 = pattern.size.0.max(line.chars().count());
+        y += 1;
+
+    }
+
+    pattern.size.1 = y;
+    pattern.normalize();
+    Ok(pattern)
+
+}
+
+// Writes a pattern in the plaintext format
+pub fn write(pattern: &Pattern) -> String {
+
+    let mut contents = String::new();
+
+    if let Some(name) = &pattern.name {
+        contents.push_str(&format!("!Name: {}\n", name));
+    }
+
+    for comment in pattern.comments.iter() {
+        contents.push_str(&format!("!{}\n", comment));
+    }
+
+    contents.push_str(&pattern.rows('O'));
+    contents
+
+}
+
+
+// tests
+#[cfg(test)]
+
+#[test]
+fn test_plaintext_round_trip() {
+
+    let glider = "!Name: Glider\n!The smallest spaceship\n.O.\n..O\nOOO\n";
+
+    let pattern = parse(glider).unwrap();
+    assert_eq!(pattern.name, Some("Glider".to_string()));
+    assert_eq!(pattern.comments, vec!["The smallest spaceship".to_string()]);
+    assert_eq!(pattern.size, (3, 3));
+    assert_eq!(pattern.cells, vec![(1, 0), (2, 1), (0, 2), (1, 2), (2, 2)]);
+
+    assert_eq!(write(&pattern), "!Name: Glider\n!The smallest spaceship\n.O\n..O\nOOO\n");
+    assert_eq!(parse(&write(&pattern)).unwrap(), pattern);
+
+    assert!(matches!(parse(".O.\n.x."), Err(PatternError::UnexpectedCharacter { character: 'x', line: 2 })));
+
+}
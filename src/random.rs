@@ -0,0 +1,69 @@
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+// Small seeded pseudo random number generator (SplitMix64). Runs started from the same seed
+// always produce the same cells, which keeps random fills reproducible
+#[derive(Debug, Clone)]
+pub struct Random {
+    state: u64
+}
+
+impl Random {
+
+    pub fn new(seed: u64) -> Self {
+        Self { state: seed }
+    }
+
+    // A seed that changes from one run to the next
+    pub fn seed_from_time() -> u64 {
+
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|duration| duration.as_nanos() as u64)
+            .unwrap_or(0)
+
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+
+    }
+
+    // Uniform in [0, 1)
+    pub fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    // True with the given probability
+    pub fn chance(&mut self, probability: f64) -> bool {
+        self.next_f64() < probability
+    }
+
+}
+
+
+// tests
+#[cfg(test)]
+
+#[test]
+fn test_random_is_reproducible() {
+
+    let mut first = Random::new(42);
+    let mut second = Random::new(42);
+    let mut other = Random::new(43);
+
+    let sequence: Vec<u64> = (0..8).map(|_| first.next_u64()).collect();
+    assert_eq!(sequence, (0..8).map(|_| second.next_u64()).collect::<Vec<u64>>());
+    assert_ne!(sequence, (0..8).map(|_| other.next_u64()).collect::<Vec<u64>>());
+
+    // roughly the requested share of hits
+    let hits = (0..10_000).filter(|_| first.chance(0.3)).count();
+    assert!(hits > 2_800 && hits < 3_200);
+
+}
@@ -0,0 +1,284 @@
+
+use crate::pattern::{Pattern, PatternError};
+
+// Longest line written to an RLE body, as recommended by the format
+const MAX_LINE_LENGTH: usize = 70;
+
+// Most living cells read from a single pattern, so a bogus run count can not use up the memory
+pub const MAX_CELLS: usize = 1 << 26;
+
+// Reads a pattern in the run length encoded format:
+//
+//   #N Glider
+//   x = 3, y = 3, rule = B3/S23
+//   bob$2bo$3o!
+//
+// `b` is a dead cell, `o` a living one, `$` ends a row and `!` ends the pattern. Each of them
+// can be preceded by a run count
+pub fn parse(contents: &str) -> Result<Pattern, PatternError> {
+
+    let mut pattern = Pattern::default();
+    let mut header_seen = false;
+
+    let mut position = (0, 0);
+    let mut run: usize = 0;
+
+    'lines: for (index, line) in contents.lines().enumerate() {
+
+        let line = line.trim();
+        let line_number = index + 1;
+
+        if line.is_empty() {
+            continue;
+        }
+
+        if !header_seen {
+
+            if let Some(comment) = line.strip_prefix('#') {
+                parse_comment(comment, &mut pattern)?;
+                continue;
+            }
+
+            parse_header(line, &mut pattern)?;
+            header_seen = true;
+            continue;
+
+        }
+
+        // runs may not take the position past what a usize holds, nor add more than MAX_CELLS
+        let too_long = || PatternError::RunTooLong { line: line_number };
+        let after = |coordinate: usize, run: usize| coordinate.checked_add(run.max(1));
+
+        for character in line.chars() {
+
+            match character {
+                '0'..='9' => {
+                    run = run.checked_mul(10)
+                        .and_then(|run| run.checked_add(character.to_digit(10).unwrap_or(0) as usize))
+                        .ok_or_else(too_long)?;
+                    continue;
+                },
+                'b' | '.' => position.0 = after(position.0, run).ok_or_else(too_long)?,
+                'o' | 'A' => {
+                    let end = after(position.0, run)
+                        .filter(|_| run.max(1) <= MAX_CELLS - pattern.cells.len())
+                        .ok_or_else(too_long)?;
+                    pattern.cells.extend((position.0..end).map(|x| (x, position.1)));
+                    position.0 = end;
+                },
+                '$' => position = (0, after(position.1, run).ok_or_else(too_long)?),
+                '!' => break 'lines,
+                c if c.is_whitespace() => continue,
+                character => return Err(PatternError::UnexpectedCharacter { character, line: line_number })
+            }
+
+            run = 0;
+
+        }
+
+    }
+
+    if !header_seen {
+        return Err(PatternError::InvalidHeader(String::new()));
+    }
+
+    pattern.normalize();
+    Ok(pattern)
+
+}
+
+// Writes a pattern in the run length encoded format, comments and name first
+pub fn write(pattern: &Pattern) -> String {
+
+    let mut contents = String::new();
+
+    if let Some(name) = &pattern.name {
+        contents.push_str(&format!("#N {}\n", name));
+    }
+
+    for comment in pattern.comments.iter() {
+        contents.push_str(&format!("#C {}\n", comment));
+    }
+
+    contents.push_str(&format!("x = {}, y = {}", pattern.size.0, pattern.size.1));
+    if let Some(rule) = &pattern.rule {
+        contents.push_str(&format!(", rule = {}", rule));
+    }
+    contents.push('\n');
+
+    // collect (count, tag) runs, leaving out dead cells at the end of rows and empty rows at
+    // the end of the pattern
+    let mut runs: Vec<(usize, char)> = Vec::new();
+    let mut push = |count: usize, tag: char| {
+        match runs.last_mut() {
+            Some((last_count, last_tag)) if *last_tag == tag => *last_count += count,
+            _ => runs.push((count, tag))
+        }
+    };
+
+    let mut cells = pattern.cells.clone();
+    cells.sort_by_key(|(x, y)| (*y, *x));
+
+    let mut position = (0, 0);
+    for (x, y) in cells {
+
+        if y > position.1 {
+            push(y - position.1, '$');
+            position = (0, y);
+        }
+
+        if x > position.0 {
+            push(x - position.0, 'b');
+        }
+
+        push(1, 'o');
+        position.0 = x + 1;
+
+    }
+
+    push(1, '!');
+
+    // wrap the body so no line is longer than the recommended length
+    let mut line = String::new();
+    for (count, tag) in runs {
+
+        let item = if count == 1 { tag.to_string() } else { format!("{}{}", count, tag) };
+
+        if line.len() + item.len() > MAX_LINE_LENGTH {
+            contents.push_str(&line);
+            contents.push('\n');
+            line.clear();
+        }
+
+        line.push_str(&item);
+
+    }
+
+    contents.push_str(&line);
+    contents.push('\n');
+    contents
+
+}
+
+fn parse_comment(comment: &str, pattern: &mut Pattern) -> Result<(), PatternError> {
+
+    let mut chars = comment.chars();
+    let kind = chars.next();
+    let text = chars.as_str().trim().to_string();
+
+    match kind {
+        Some('N') => pattern.name = Some(text),
+        Some('r') => pattern.rule = Some(text.parse()?),
+        // positions from #P and #R are not needed since patterns are placed explicitly
+        Some('P') | Some('R') => (),
+        Some(_) => pattern.comments.push(text),
+        None => ()
+    }
+
+    Ok(())
+
+}
+
+fn parse_header(line: &str, pattern: &mut Pattern) -> Result<(), PatternError> {
+
+    let invalid = || PatternError::InvalidHeader(line.to_string());
+
+    for field in line.split(',') {
+
+        let mut parts = field.splitn(2, '=');
+        let key = parts.next().unwrap_or("").trim();
+        let value = parts.next().ok_or_else(invalid)?.trim();
+
+        match key {
+            "x" => pattern.size.0 = value.parse().map_err(|_| invalid())?,
+            "y" => pattern.size.1 = value.parse().map_err(|_| invalid())?,
+            "rule" => pattern.rule = Some(value.parse()?),
+            _ => return Err(invalid())
+        }
+
+    }
+
+    Ok(())
+
+}
+
+
+// tests
+#[cfg(test)]
+use crate::{grid, rule};
+
+#[cfg(test)]
+const GOSPER_GLIDER_GUN: &str = "#N Gosper glider gun
+#C This was the first gun discovered.
+x = 36, y = 9, rule = B3/S23
+24bo$22bobo$12b2o6b2o12b2o$11bo3bo4b2o12b2o$2o8bo5bo3b2o$2o8bo3bob2o4b
+obo$10bo5bo7bo$11bo3bo$12b2o!
+";
+
+#[test]
+fn test_rle_parse() {
+
+    let pattern = parse(GOSPER_GLIDER_GUN).unwrap();
+
+    assert_eq!(pattern.name, Some("Gosper glider gun".to_string()));
+    assert_eq!(pattern.comments, vec!["This was the first gun discovered.".to_string()]);
+    assert_eq!(pattern.rule, Some(rule::Rule::default()));
+    assert_eq!(pattern.size, (36, 9));
+    assert_eq!(pattern.population(), 36);
+    assert_eq!(pattern.cells[0], (24, 0));
+
+}
+
+#[test]
+fn test_rle_parse_errors() {
+
+    assert!(matches!(parse(""), Err(PatternError::InvalidHeader(_))));
+    assert!(matches!(parse("x = three, y = 3\nbo$!"), Err(PatternError::InvalidHeader(_))));
+    assert!(matches!(parse("x = 3, y = 3, rule = B9/S23\nbo$!"), Err(PatternError::InvalidRule(_))));
+    assert!(matches!(parse("x = 3, y = 3\nbo$\n2bq!"),
+        Err(PatternError::UnexpectedCharacter { character: 'q', line: 3 })));
+
+    // run counts past what a usize holds, or that would add too many cells
+    assert!(matches!(parse("x = 3, y = 3\n99999999999999999999999b!"), Err(PatternError::RunTooLong { line: 2 })));
+    assert!(matches!(parse("x = 3, y = 3\n18446744073709551615bbo!"), Err(PatternError::RunTooLong { line: 2 })));
+    assert!(matches!(parse("x = 3, y = 3\n18446744073709551615$$o!"), Err(PatternError::RunTooLong { line: 2 })));
+    assert!(matches!(parse("x = 3, y = 3\n9999999999o!"), Err(PatternError::RunTooLong { line: 2 })));
+    assert!(matches!(parse("x = 3, y = 3\n18446744073709551615o!"), Err(PatternError::RunTooLong { line: 2 })));
+
+}
+
+#[test]
+fn test_rle_round_trip() {
+
+    let pattern = parse(GOSPER_GLIDER_GUN).unwrap();
+
+    // through text
+    let written = write(&pattern);
+    assert_eq!(parse(&written).unwrap(), pattern);
+    assert!(written.lines().all(|line| line.len() <= MAX_LINE_LENGTH));
+
+    // through a grid, at an offset
+    let mut cell_grid = grid::Grid::new((100, 50));
+    pattern.place(&mut cell_grid, (40, 20)).unwrap();
+    assert!(cell_grid.is_alive(&(40 + 24, 20)));
+
+    let saved = Pattern::from_grid(&cell_grid);
+    assert_eq!(saved.cells, pattern.cells);
+    assert_eq!(saved.size, pattern.size);
+    assert_eq!(parse(&write(&saved)).unwrap(), saved);
+
+    // the pattern has to fit
+    assert!(matches!(pattern.place(&mut cell_grid, (70, 45)), Err(PatternError::DoesNotFit)));
+    assert!(matches!(pattern.place(&mut cell_grid, (usize::MAX, 0)), Err(PatternError::DoesNotFit)));
+    assert!(matches!(pattern.place(&mut cell_grid, (0, usize::MAX - 3)), Err(PatternError::DoesNotFit)));
+
+}
+
+#[test]
+fn test_rle_write_empty_rows() {
+
+    let pattern = Pattern::from_cells(vec![(0, 0), (1, 0), (2, 0), (2, 3)]);
+
+    assert_eq!(write(&pattern), "x = 3, y = 4\n3o3$2bo!\n");
+
+}
@@ -0,0 +1,275 @@
+
+use std::fmt;
+use std::str::FromStr;
+
+use crate::cell;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum RuleParseError {
+    Empty,
+    MissingSeparator,
+    InvalidCharacter(char),
+    InvalidNeighborCount(char),
+    DuplicateNeighborCount(char)
+}
+
+impl fmt::Display for RuleParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RuleParseError::Empty => write!(f, "Empty rulestring"),
+            RuleParseError::MissingSeparator => {
+                write!(f, "Rulestring must look like B3/S23 or 23/3")
+            },
+            RuleParseError::InvalidCharacter(c) => {
+                write!(f, "Unexpected character '{}' in rulestring", c)
+            },
+            RuleParseError::InvalidNeighborCount(c) => {
+                write!(f, "Neighbor count '{}' is out of range (0-8)", c)
+            },
+            RuleParseError::DuplicateNeighborCount(c) => {
+                write!(f, "Neighbor count '{}' appears more than once", c)
+            }
+        }
+    }
+}
+
+impl std::error::Error for RuleParseError {}
+
+
+// Outer-totalistic rule on the Moore neighborhood: a dead cell is born when its number of living
+// neighbors is in `birth`, a living cell survives when it is in `survival`, otherwise it dies
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Rule {
+    birth: [bool; 9],
+    survival: [bool; 9]
+}
+
+impl Rule {
+
+    pub fn new(birth: &[usize], survival: &[usize]) -> Self {
+
+        let mut rule = Self {
+            birth: [false; 9],
+            survival: [false; 9]
+        };
+
+        for count in birth.iter().filter(|count| **count < 9) {
+            rule.birth[*count] = true;
+        }
+
+        for count in survival.iter().filter(|count| **count < 9) {
+            rule.survival[*count] = true;
+        }
+
+        rule
+
+    }
+
+    pub fn is_born(&self, living_neighbors: usize) -> bool {
+        living_neighbors < 9 && self.birth[living_neighbors]
+    }
+
+    pub fn survives(&self, living_neighbors: usize) -> bool {
+        living_neighbors < 9 && self.survival[living_neighbors]
+    }
+
+    pub fn next_state(&self, state: &cell::CellState, living_neighbors: usize) -> cell::CellState {
+
+        let alive = match state {
+            cell::CellState::Alive => self.survives(living_neighbors),
+            cell::CellState::Dead => self.is_born(living_neighbors)
+        };
+
+        if alive {
+            cell::CellState::Alive
+        } else {
+            cell::CellState::Dead
+        }
+
+    }
+
+    // Older S/B notation used by some pattern formats, e.g. "23/3" for Conway's Game of Life
+    pub fn to_survival_birth(&self) -> String {
+
+        let digits = |counts: &[bool; 9]| -> String {
+            (0..9).filter(|count| counts[*count]).map(|count| count.to_string()).collect()
+        };
+
+        format!("{}/{}", digits(&self.survival), digits(&self.birth))
+
+    }
+
+}
+
+// Conway's Game of Life, B3/S23
+impl Default for Rule {
+    fn default() -> Self {
+        Rule::new(&[3], &[2, 3])
+    }
+}
+
+impl fmt::Display for Rule {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+
+        write!(f, "B")?;
+        for count in (0..9).filter(|count| self.birth[*count]) {
+            write!(f, "{}", count)?;
+        }
+
+        write!(f, "/S")?;
+        for count in (0..9).filter(|count| self.survival[*count]) {
+            write!(f, "{}", count)?;
+        }
+
+        Ok(())
+
+    }
+}
+
+// Accepts B/S notation ("B36/S23", "b3s23") and the older S/B notation ("23/36")
+impl FromStr for Rule {
+    type Err = RuleParseError;
+
+    fn from_str(rulestring: &str) -> Result<Self, Self::Err> {
+
+        let rulestring = rulestring.trim();
+
+        if rulestring.is_empty() {
+            return Err(RuleParseError::Empty);
+        }
+
+        let (birth, survival) = if rulestring.starts_with(['B', 'b']) {
+
+            // B/S notation, the slash is optional
+            let body = &rulestring[1..];
+            let split = match body.find(['S', 's']) {
+                Some(index) => index,
+                None => return Err(RuleParseError::MissingSeparator)
+            };
+
+            let birth = body[..split].trim_end_matches('/');
+            (birth, &body[split + 1..])
+
+        } else if rulestring.starts_with(['S', 's']) {
+
+            // S/B notation with letters
+            let body = &rulestring[1..];
+            let split = match body.find(['B', 'b']) {
+                Some(index) => index,
+                None => return Err(RuleParseError::MissingSeparator)
+            };
+
+            let survival = body[..split].trim_end_matches('/');
+            (&body[split + 1..], survival)
+
+        } else {
+
+            // S/B notation without letters, survival comes first
+            let mut parts = rulestring.split('/');
+            let survival = parts.next().unwrap_or("");
+            let birth = match parts.next() {
+                Some(birth) => birth,
+                None => return Err(RuleParseError::MissingSeparator)
+            };
+
+            if parts.next().is_some() {
+                return Err(RuleParseError::InvalidCharacter('/'));
+            }
+
+            (birth, survival)
+
+        };
+
+        Ok(Self {
+            birth: parse_counts(birth)?,
+            survival: parse_counts(survival)?
+        })
+
+    }
+}
+
+fn parse_counts(digits: &str) -> Result<[bool; 9], RuleParseError> {
+
+    let mut counts = [false; 9];
+
+    for c in digits.chars() {
+
+        let count = match c.to_digit(10) {
+            Some(count) => count as usize,
+            None => return Err(RuleParseError::InvalidCharacter(c))
+        };
+
+        if count > 8 {
+            return Err(RuleParseError::InvalidNeighborCount(c));
+        }
+
+        if counts[count] {
+            return Err(RuleParseError::DuplicateNeighborCount(c));
+        }
+
+        counts[count] = true;
+
+    }
+
+    Ok(counts)
+
+}
+
+
+// tests
+#[cfg(test)]
+
+#[test]
+fn test_rule_default_is_conway() {
+
+    let rule = Rule::default();
+
+    assert_eq!(rule, Rule::new(&[3], &[2, 3]));
+    assert_eq!(rule.to_string(), "B3/S23");
+
+}
+
+#[test]
+fn test_rule_parse_notations() {
+
+    let highlife: Rule = "B36/S23".parse().unwrap();
+    assert_eq!(highlife, Rule::new(&[3, 6], &[2, 3]));
+
+    assert_eq!("b36s23".parse::<Rule>().unwrap(), highlife);
+    assert_eq!("23/36".parse::<Rule>().unwrap(), highlife);
+    assert_eq!("S23/B36".parse::<Rule>().unwrap(), highlife);
+
+    let seeds: Rule = "B2/S".parse().unwrap();
+    assert_eq!(seeds, Rule::new(&[2], &[]));
+    assert_eq!("/2".parse::<Rule>().unwrap(), seeds);
+
+    let day_and_night: Rule = "B3678/S34678".parse().unwrap();
+    assert_eq!(day_and_night.to_string(), "B3678/S34678");
+    assert_eq!(day_and_night.to_survival_birth(), "34678/3678");
+
+}
+
+#[test]
+fn test_rule_parse_errors() {
+
+    assert_eq!("".parse::<Rule>(), Err(RuleParseError::Empty));
+    assert_eq!("B3".parse::<Rule>(), Err(RuleParseError::MissingSeparator));
+    assert_eq!("23".parse::<Rule>(), Err(RuleParseError::MissingSeparator));
+    assert_eq!("B39/S23".parse::<Rule>(), Err(RuleParseError::InvalidNeighborCount('9')));
+    assert_eq!("B3x/S23".parse::<Rule>(), Err(RuleParseError::InvalidCharacter('x')));
+    assert_eq!("B33/S23".parse::<Rule>(), Err(RuleParseError::DuplicateNeighborCount('3')));
+
+}
+
+#[test]
+fn test_rule_next_state() {
+
+    let rule = Rule::default();
+
+    assert_eq!(rule.next_state(&cell::CellState::Dead, 3), cell::CellState::Alive);
+    assert_eq!(rule.next_state(&cell::CellState::Dead, 2), cell::CellState::Dead);
+    assert_eq!(rule.next_state(&cell::CellState::Alive, 2), cell::CellState::Alive);
+    assert_eq!(rule.next_state(&cell::CellState::Alive, 1), cell::CellState::Dead);
+    assert_eq!(rule.next_state(&cell::CellState::Alive, 4), cell::CellState::Dead);
+
+}
@@ -0,0 +1,405 @@
+
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::census::{Census, Kind};
+use crate::cell;
+use crate::cli::{CliError, Options};
+use crate::game::{self, Simulation};
+use crate::pattern::PatternError;
+use crate::random::Random;
+use crate::rule::Rule;
+use crate::soup::{Soup, Symmetry, DEFAULT_DENSITY};
+use crate::universe::Universe;
+
+// Size of every soup unless given, the same as apgsearch uses
+pub const DEFAULT_SOUP_SIZE: (usize, usize) = (16, 16);
+
+// Generations a soup gets to settle down before it is given up on
+pub const DEFAULT_GENERATIONS: u64 = 10_000;
+
+// Objects found this many times or fewer are listed as rare, with the seeds they came from
+pub const RARE_COUNT: u64 = 3;
+
+// Seeds kept for every object, the lowest ones
+pub const SEEDS_KEPT: usize = 5;
+
+// A soup has settled once its population repeats with a period up to this, which still works
+// while gliders fly away. Every period has to repeat for a while before it counts
+const MAX_POPULATION_PERIOD: usize = 60;
+const MIN_SETTLED_GENERATIONS: usize = 120;
+const PERIOD_REPEATS: usize = 4;
+
+// Generations between checks for a settled population
+const CHECK_INTERVAL: u64 = 30;
+
+// Runs many soups one after the other, from a first seed, on an unbounded universe
+#[derive(Debug, Clone)]
+pub struct Search {
+    pub first: Soup,
+    pub soups: u64,
+    pub size: (usize, usize),
+    pub rule: Rule,
+    pub generations: u64,
+    pub threads: usize
+}
+
+impl Search {
+
+    pub fn new(first: Soup, soups: u64) -> Self {
+
+        Self {
+            first,
+            soups,
+            size: DEFAULT_SOUP_SIZE,
+            rule: Rule::default(),
+            generations: DEFAULT_GENERATIONS,
+            threads: thread::available_parallelism().map(|count| count.get()).unwrap_or(1)
+        }
+
+    }
+
+    pub fn with_size(mut self, size: (usize, usize)) -> Self {
+        self.size = size;
+        self
+    }
+
+    pub fn with_rule(mut self, rule: Rule) -> Self {
+        self.rule = rule;
+        self
+    }
+
+    pub fn with_generations(mut self, generations: u64) -> Self {
+        self.generations = generations;
+        self
+    }
+
+    pub fn with_threads(mut self, threads: usize) -> Self {
+        self.threads = threads.max(1);
+        self
+    }
+
+    // Runs every soup, spread over the threads. The report does not depend on how many there are
+    pub fn run(&self) -> Report {
+
+        let start = Instant::now();
+        let next = AtomicU64::new(0);
+
+        let partials: Vec<Report> = thread::scope(|scope| {
+
+            let handles: Vec<_> = (0..self.threads)
+                .map(|_| scope.spawn(|| {
+                    let mut partial = Report::new(self.clone());
+                    loop {
+                        let index = next.fetch_add(1, Ordering::Relaxed);
+                        if index >= self.soups {
+                            break partial;
+                        }
+
+                        let soup = Soup { seed: self.first.seed.wrapping_add(index), ..self.first };
+                        partial.add(soup.seed, self.run_soup(&soup));
+                    }
+                }))
+                .collect();
+
+            handles.into_iter().map(|handle| handle.join().expect("a search thread panicked")).collect()
+
+        });
+
+        let mut report = Report::new(self.clone());
+        for partial in partials {
+            report.merge(partial);
+        }
+
+        report.elapsed = start.elapsed();
+        report
+
+    }
+
+    // Census of a soup once it settled, None if it did not in time
+    pub fn run_soup(&self, soup: &Soup) -> Option<Census> {
+
+        let mut universe = Universe::new();
+        for (x, y) in soup.cells(self.size) {
+            universe.set_state(&(x as i64, y as i64), cell::CellState::Alive);
+        }
+
+        let mut game = game::UnboundedLife::new(universe, self.rule);
+        settle(&mut game, self.generations)?;
+        Some(Census::of_simulation(&game))
+
+    }
+
+}
+
+// Steps a simulation until its population repeats, returning the generation that happened at
+fn settle(game: &mut dyn Simulation, limit: u64) -> Option<u64> {
+
+    let mut populations = vec![game.population()];
+
+    for _ in 0..limit {
+
+        game.update().ok()?;
+        populations.push(game.population());
+
+        if game.get_evolution().is_multiple_of(CHECK_INTERVAL) && population_period(&populations).is_some() {
+            return Some(game.get_evolution());
+        }
+
+    }
+
+    None
+
+}
+
+// Shortest period the latest populations repeat with
+fn population_period(populations: &[usize]) -> Option<usize> {
+
+    (1..=MAX_POPULATION_PERIOD).find(|period| {
+        let window = (period * PERIOD_REPEATS).max(MIN_SETTLED_GENERATIONS) + period;
+        populations.len() >= window && {
+            let recent = &populations[populations.len() - window..];
+            (*period..recent.len()).all(|index| recent[index] == recent[index - period])
+        }
+    })
+
+}
+
+// Number of times an object was found, and the lowest seeds it was found in
+#[derive(Debug, Clone, PartialEq)]
+pub struct Tally {
+    pub code: String,
+    pub kind: Kind,
+    pub name: Option<&'static str>,
+    pub count: u64,
+    pub seeds: Vec<u64>
+}
+
+// Objects found over all the soups of a search
+#[derive(Debug, Clone)]
+pub struct Report {
+    pub search: Search,
+    pub settled: u64,
+    // seeds of the soups that did not settle in time
+    pub unsettled: Vec<u64>,
+    pub elapsed: Duration,
+    tallies: HashMap<String, Tally>
+}
+
+impl Report {
+
+    fn new(search: Search) -> Self {
+
+        Self {
+            search,
+            settled: 0,
+            unsettled: Vec::new(),
+            elapsed: Duration::from_secs(0),
+            tallies: HashMap::new()
+        }
+
+    }
+
+    fn add(&mut self, seed: u64, census: Option<Census>) {
+
+        let census = match census {
+            Some(census) => census,
+            None => {
+                self.unsettled.push(seed);
+                return;
+            }
+        };
+
+        self.settled += 1;
+        for object in census.objects.iter() {
+            let tally = self.tallies.entry(object.code.clone()).or_insert_with(|| Tally {
+                code: object.code.clone(),
+                kind: object.kind,
+                name: object.name(),
+                count: 0,
+                seeds: Vec::new()
+            });
+
+            tally.count += 1;
+            // seeds come in increasing order, so the first ones are the lowest
+            if tally.seeds.len() < SEEDS_KEPT && tally.seeds.last() != Some(&seed) {
+                tally.seeds.push(seed);
+            }
+        }
+
+    }
+
+    fn merge(&mut self, other: Report) {
+
+        self.settled += other.settled;
+        self.unsettled.extend(other.unsettled);
+        self.unsettled.sort_unstable();
+
+        for (code, other) in other.tallies {
+            let tally = self.tallies.entry(code).or_insert_with(|| Tally { count: 0, seeds: Vec::new(), ..other.clone() });
+            tally.count += other.count;
+            tally.seeds.extend(other.seeds);
+            tally.seeds.sort_unstable();
+            tally.seeds.dedup();
+            tally.seeds.truncate(SEEDS_KEPT);
+        }
+
+    }
+
+    pub fn objects(&self) -> u64 {
+        self.tallies.values().map(|tally| tally.count).sum()
+    }
+
+    // Every object found, most common first
+    pub fn tallies(&self) -> Vec<&Tally> {
+
+        let mut tallies: Vec<&Tally> = self.tallies.values().collect();
+        tallies.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.code.len().cmp(&b.code.len())).then_with(|| a.code.cmp(&b.code)));
+        tallies
+
+    }
+
+    // Objects found RARE_COUNT times or fewer, rarest first
+    pub fn rare(&self) -> Vec<&Tally> {
+
+        let mut rare: Vec<&Tally> = self.tallies().into_iter().filter(|tally| tally.count <= RARE_COUNT).collect();
+        rare.reverse();
+        rare
+
+    }
+
+}
+
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+
+        let search = &self.search;
+        write!(f, "searched {} soups of {}x{} at density {:.2}, {}, ", search.soups, search.size.0, search.size.1,
+            search.first.density, search.first.symmetry)?;
+
+        // an empty search ran no seed at all
+        if search.soups > 0 {
+            write!(f, "seeds {} to {}, ", search.first.seed, search.first.seed.wrapping_add(search.soups - 1))?;
+        }
+        writeln!(f, "rule {}", search.rule)?;
+
+        let seconds = self.elapsed.as_secs_f64();
+        let rate = if seconds > 0.0 { search.soups as f64 / seconds } else { 0.0 };
+        writeln!(f, "settled {}, objects {}, {:.1} s ({:.1} soups/sec)", self.settled, self.objects(), seconds, rate)?;
+
+        let tallies = self.tallies();
+        let width = tallies.iter().map(|tally| tally.code.len()).max().unwrap_or(0).max(4) + 2;
+
+        let row = |f: &mut fmt::Formatter, tally: &Tally, seeds: bool| -> fmt::Result {
+            write!(f, "{:>8}  {:<28}{:<16}", tally.count, tally.kind.to_string(), tally.name.unwrap_or(""))?;
+            if seeds {
+                let seeds: Vec<String> = tally.seeds.iter().map(|seed| seed.to_string()).collect();
+                writeln!(f, "{:<width$}{}", tally.code, seeds.join(", "), width = width)
+            } else {
+                writeln!(f, "{}", tally.code)
+            }
+        };
+
+        writeln!(f, "\nrare objects, found {} times or fewer", RARE_COUNT)?;
+        writeln!(f, "{:>8}  {:<28}{:<16}{:<width$}seeds", "count", "kind", "name", "code", width = width)?;
+        for tally in self.rare() {
+            row(f, tally, true)?;
+        }
+
+        writeln!(f, "\nall objects")?;
+        writeln!(f, "{:>8}  {:<28}{:<16}code", "count", "kind", "name")?;
+        for tally in tallies {
+            row(f, tally, false)?;
+        }
+
+        if !self.unsettled.is_empty() {
+            let seeds: Vec<String> = self.unsettled.iter().map(|seed| seed.to_string()).collect();
+            writeln!(f, "\nsoups that did not settle in {} generations: {}", search.generations, seeds.join(", "))?;
+        }
+
+        Ok(())
+
+    }
+}
+
+// Runs the search described by the options, and writes the report to the output file if given
+pub fn run(options: &Options) -> Result<Report, CliError> {
+
+    let rule = options.rule.unwrap_or_default();
+    if rule.is_born(0) {
+        return Err(CliError::UnboundedBirthOnZero);
+    }
+
+    let first = Soup::new(options.seed.unwrap_or_else(Random::seed_from_time))
+        .with_density(options.fill.unwrap_or(DEFAULT_DENSITY))
+        .with_symmetry(options.symmetry.unwrap_or(Symmetry::C1));
+
+    let mut search = Search::new(first, options.search.unwrap_or(0))
+        .with_size(options.soup_size.unwrap_or(DEFAULT_SOUP_SIZE))
+        .with_rule(rule)
+        .with_generations(options.generations.unwrap_or(DEFAULT_GENERATIONS));
+
+    if let Some(threads) = options.threads {
+        search = search.with_threads(threads);
+    }
+
+    let report = search.run();
+    if let Some(path) = &options.output {
+        fs::write(path, report.to_string()).map_err(PatternError::from)?;
+    }
+
+    Ok(report)
+
+}
+
+
+// tests
+#[test]
+fn test_population_period() {
+
+    let mut populations = vec![7; MIN_SETTLED_GENERATIONS];
+    assert_eq!(population_period(&populations), None);
+    populations.push(7);
+    assert_eq!(population_period(&populations), Some(1));
+
+    // a blinker next to a pulsar, which changes population every generation
+    let populations: Vec<usize> = (0..400).map(|generation| [48, 56, 72][generation % 3] + 3).collect();
+    assert_eq!(population_period(&populations), Some(3));
+    let mut chaotic = populations.clone();
+    chaotic.push(1);
+    assert_eq!(population_period(&chaotic), None);
+
+}
+
+#[test]
+fn test_search_report() {
+
+    let search = Search::new(Soup::new(100), 6).with_generations(3000);
+    let report = search.clone().with_threads(1).run();
+    assert_eq!(report.settled + report.unsettled.len() as u64, 6);
+    assert!(report.settled > 0);
+
+    // the results do not depend on the threads
+    let threaded = search.with_threads(3).run();
+    assert_eq!(threaded.tallies(), report.tallies());
+    assert_eq!(threaded.unsettled, report.unsettled);
+
+    // blocks and blinkers are the most common objects
+    let common: Vec<&str> = report.tallies().iter().take(2).filter_map(|tally| tally.name).collect();
+    assert!(common.contains(&"block") || common.contains(&"blinker"));
+    assert!(report.tallies().iter().all(|tally| tally.seeds.len() <= SEEDS_KEPT && tally.seeds.iter().all(|seed| (100..106).contains(seed))));
+
+    let text = report.to_string();
+    assert!(text.starts_with("searched 6 soups of 16x16 at density 0.50, C1, seeds 100 to 105, rule B3/S23\n"));
+    assert!(text.contains("\nrare objects, found 3 times or fewer\n"));
+    assert!(report.rare().iter().all(|tally| tally.count <= RARE_COUNT));
+
+    let empty = Search::new(Soup::new(100), 0).run();
+    assert_eq!(empty.settled, 0);
+    assert!(empty.to_string().starts_with("searched 0 soups of 16x16 at density 0.50, C1, rule B3/S23\n"));
+
+}
@@ -0,0 +1,213 @@
+
+use std::fmt;
+use std::str::FromStr;
+
+use crate::cell;
+use crate::grid;
+use crate::random::Random;
+
+// Share of living cells when no density is given
+pub const DEFAULT_DENSITY: f64 = 0.5;
+
+// Symmetries a soup can be made with, named as in apgsearch
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Symmetry {
+    // no symmetry
+    C1,
+    // the same when turned half way around
+    C2,
+    // the same when turned a quarter of the way around
+    C4,
+    // mirrored left to right
+    D2,
+    // mirrored left to right and top to bottom
+    D4,
+    // mirrored along both axes and both diagonals
+    D8
+}
+
+pub const SYMMETRIES: [Symmetry; 6] = [Symmetry::C1, Symmetry::C2, Symmetry::C4, Symmetry::D2, Symmetry::D4, Symmetry::D8];
+
+impl Symmetry {
+
+    // Symmetries turning cells a quarter of the way or along the diagonals need a square
+    pub fn needs_square(&self) -> bool {
+        matches!(self, Symmetry::C4 | Symmetry::D8)
+    }
+
+    // Cells that have to share the state of a cell in a soup of the given size
+    fn images(&self, position: (usize, usize), size: (usize, usize)) -> Vec<(usize, usize)> {
+
+        let (x, y) = position;
+        let (mx, my) = (size.0 - 1 - x, size.1 - 1 - y);
+
+        match self {
+            Symmetry::C1 => vec![(x, y)],
+            Symmetry::C2 => vec![(x, y), (mx, my)],
+            Symmetry::C4 => vec![(x, y), (my, x), (mx, my), (y, mx)],
+            Symmetry::D2 => vec![(x, y), (mx, y)],
+            Symmetry::D4 => vec![(x, y), (mx, y), (x, my), (mx, my)],
+            Symmetry::D8 => vec![(x, y), (mx, y), (x, my), (mx, my), (y, x), (y, mx), (my, x), (my, mx)]
+        }
+
+    }
+
+}
+
+impl fmt::Display for Symmetry {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+impl FromStr for Symmetry {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+
+        SYMMETRIES.iter()
+            .find(|symmetry| symmetry.to_string().eq_ignore_ascii_case(name))
+            .copied()
+            .ok_or_else(|| name.to_string())
+
+    }
+}
+
+// Random cells filling a rectangle. The same seed always gives the same soup
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Soup {
+    pub seed: u64,
+    pub density: f64,
+    pub symmetry: Symmetry
+}
+
+impl Soup {
+
+    pub fn new(seed: u64) -> Self {
+
+        Self {
+            seed,
+            density: DEFAULT_DENSITY,
+            symmetry: Symmetry::C1
+        }
+
+    }
+
+    pub fn with_density(mut self, density: f64) -> Self {
+        self.density = density.clamp(0.0, 1.0);
+        self
+    }
+
+    pub fn with_symmetry(mut self, symmetry: Symmetry) -> Self {
+        self.symmetry = symmetry;
+        self
+    }
+
+    // The following soup, with the same settings
+    pub fn next(&self) -> Self {
+        Self { seed: self.seed.wrapping_add(1), ..*self }
+    }
+
+    // Living cells of a soup of the given size, from its top left corner. Symmetries that need
+    // a square use the largest one that fits, in the middle
+    pub fn cells(&self, size: (usize, usize)) -> Vec<(usize, usize)> {
+
+        let (square, offset) = if self.symmetry.needs_square() {
+            let side = size.0.min(size.1);
+            ((side, side), ((size.0 - side) / 2, (size.1 - side) / 2))
+        } else {
+            (size, (0, 0))
+        };
+
+        let mut random = Random::new(self.seed);
+        let mut cells = Vec::new();
+
+        // the cell coming first of those sharing a state decides for all of them
+        for y in 0..square.1 {
+            for x in 0..square.0 {
+                let images = self.symmetry.images((x, y), square);
+                if images.iter().min() == Some(&(x, y)) && random.chance(self.density) {
+                    cells.extend(images.iter().map(|(x, y)| (x + offset.0, y + offset.1)));
+                }
+            }
+        }
+
+        cells.sort_unstable();
+        cells.dedup();
+        cells
+
+    }
+
+    // Brings the cells of the soup to life in a rectangle of a grid, leaving the others alone
+    pub fn fill(&self, cell_grid: &mut grid::Grid, offset: (usize, usize), size: (usize, usize)) -> Result<(), grid::OutOfBoundsError> {
+
+        for (x, y) in self.cells(size) {
+            cell_grid.set_state(&(offset.0 + x, offset.1 + y), cell::CellState::Alive)?;
+        }
+
+        Ok(())
+
+    }
+
+}
+
+impl fmt::Display for Soup {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "seed {}, density {:.2}, {}", self.seed, self.density, self.symmetry)
+    }
+}
+
+
+// tests
+#[cfg(test)]
+use std::collections::HashSet;
+
+#[test]
+fn test_soup_symmetries() {
+
+    let size = (16, 16);
+
+    for symmetry in SYMMETRIES.iter() {
+
+        let soup = Soup::new(11).with_symmetry(*symmetry);
+        let cells = soup.cells(size);
+        let alive: HashSet<&(usize, usize)> = cells.iter().collect();
+
+        // every image of a living cell is alive too
+        for position in cells.iter() {
+            for image in symmetry.images(*position, size) {
+                assert!(alive.contains(&image), "{} soup is not symmetric", symmetry);
+            }
+        }
+
+        // about half of the cells at the default density
+        assert!(cells.len() > 256 / 4 && cells.len() < 256 * 3 / 4, "{} soup has {} cells", symmetry, cells.len());
+        assert_eq!(soup.cells(size), cells);
+
+    }
+
+    assert_eq!("d8".parse::<Symmetry>(), Ok(Symmetry::D8));
+    assert!("C3".parse::<Symmetry>().is_err());
+
+}
+
+#[test]
+fn test_soup_fill() {
+
+    let soup = Soup::new(5).with_density(0.3);
+    assert_ne!(soup.cells((10, 10)), soup.next().cells((10, 10)));
+    assert_eq!(Soup::new(5).with_density(0.0).cells((10, 10)), Vec::new());
+    assert_eq!(soup.to_string(), "seed 5, density 0.30, C1");
+
+    // only the rectangle is filled
+    let mut cell_grid = grid::Grid::new((20, 20));
+    soup.fill(&mut cell_grid, (4, 6), (10, 8)).unwrap();
+    assert_eq!(cell_grid.population(), soup.cells((10, 8)).len());
+    assert!(cell_grid.live_cells().iter().all(|(x, y)| (4..14).contains(x) && (6..14).contains(y)));
+    assert!(soup.fill(&mut cell_grid, (15, 15), (10, 10)).is_err());
+
+    // C4 uses a square in the middle of a wider soup
+    let square = Soup::new(5).with_symmetry(Symmetry::C4).cells((12, 8));
+    assert!(square.iter().all(|(x, _)| (2..10).contains(x)));
+
+}
@@ -0,0 +1,309 @@
+
+use std::collections::{HashSet, VecDeque};
+use std::mem;
+
+use crate::cell;
+use crate::game::Simulation;
+
+// Default memory used to keep changes for undo and rewind
+pub const DEFAULT_BUDGET: usize = 64 << 20;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ChangeKind {
+    // the simulation moved forward one or more generations
+    Step,
+    // cells were edited by hand
+    Edit
+}
+
+// A reversible change, stored as the cells that flipped state rather than as whole boards, so
+// the same change can be undone and redone
+#[derive(Debug, Clone, PartialEq)]
+pub struct Change {
+    pub kind: ChangeKind,
+    pub cells: Vec<(i64, i64)>,
+    pub from: u64,
+    pub to: u64
+}
+
+impl Change {
+
+    // Rough number of bytes the change takes up
+    fn cost(&self) -> usize {
+        mem::size_of::<Change>() + self.cells.len() * mem::size_of::<(i64, i64)>()
+    }
+
+}
+
+// Bounded undo and redo stacks of changes. When the stored changes go over the memory budget
+// the oldest ones are forgotten
+#[derive(Debug, Clone)]
+pub struct Timeline {
+    undo: VecDeque<Change>,
+    redo: Vec<Change>,
+    budget: usize,
+    used: usize
+}
+
+impl Default for Timeline {
+    fn default() -> Self {
+        Self::new(DEFAULT_BUDGET)
+    }
+}
+
+impl Timeline {
+
+    // A budget of zero turns the timeline off
+    pub fn new(budget: usize) -> Self {
+
+        Self {
+            undo: VecDeque::new(),
+            redo: Vec::new(),
+            budget,
+            used: 0
+        }
+
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.budget > 0
+    }
+
+    pub fn budget(&self) -> usize {
+        self.budget
+    }
+
+    // Bytes taken up by the stored changes, both undo and redo
+    pub fn used(&self) -> usize {
+        self.used
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.undo.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.redo.is_empty()
+    }
+
+    // Earliest generation that can be rewound to
+    pub fn oldest_generation(&self) -> Option<u64> {
+        self.undo.front().map(|change| change.from)
+    }
+
+    // Stores a new change. Anything that was undone can not be redone anymore
+    pub fn record(&mut self, change: Change) {
+
+        if !self.is_enabled() || (change.cells.is_empty() && change.from == change.to) {
+            return;
+        }
+
+        for undone in self.redo.drain(..) {
+            self.used -= undone.cost();
+        }
+
+        self.used += change.cost();
+        self.undo.push_back(change);
+        self.enforce_budget();
+
+    }
+
+    // Adds cells to the latest edit, so a whole mouse drag is undone at once
+    pub fn extend_edit(&mut self, cells: Vec<(i64, i64)>, generation: u64) {
+
+        match self.undo.back_mut() {
+            Some(change) if change.kind == ChangeKind::Edit && change.to == generation && self.redo.is_empty() => {
+                self.used += cells.len() * mem::size_of::<(i64, i64)>();
+                change.cells.extend(cells);
+                self.enforce_budget();
+            },
+            _ => self.record(Change { kind: ChangeKind::Edit, cells, from: generation, to: generation })
+        }
+
+    }
+
+    // Reverts the latest change, returning what kind it was
+    pub fn undo(&mut self, game: &mut dyn Simulation) -> Option<ChangeKind> {
+
+        let change = self.undo.pop_back()?;
+        apply(game, &change.cells, change.from);
+
+        let kind = change.kind;
+        self.redo.push(change);
+        Some(kind)
+
+    }
+
+    // Applies the latest undone change again
+    pub fn redo(&mut self, game: &mut dyn Simulation) -> Option<ChangeKind> {
+
+        let change = self.redo.pop()?;
+        apply(game, &change.cells, change.to);
+
+        let kind = change.kind;
+        self.undo.push_back(change);
+        Some(kind)
+
+    }
+
+    // Undoes changes until the simulation is back at a generation, or as far as the stored
+    // changes go. Edits made during that generation are kept. Returns the generation reached,
+    // which is earlier than asked for when a jump skipped over it
+    pub fn rewind_to(&mut self, game: &mut dyn Simulation, generation: u64) -> u64 {
+
+        while game.get_evolution() > generation && self.undo(game).is_some() {}
+
+        game.get_evolution()
+
+    }
+
+    fn enforce_budget(&mut self) {
+
+        while self.used > self.budget {
+            match self.undo.pop_front() {
+                Some(change) => self.used -= change.cost(),
+                None => break
+            }
+        }
+
+        // a change too large for the budget on its own can not be kept either
+        if self.used > self.budget {
+            self.redo.clear();
+            self.used = 0;
+        }
+
+    }
+
+}
+
+// Cells that differ between two sets of living cells
+pub fn toggled(before: &[(i64, i64)], after: &[(i64, i64)]) -> Vec<(i64, i64)> {
+
+    let before: HashSet<&(i64, i64)> = before.iter().collect();
+    let after: HashSet<&(i64, i64)> = after.iter().collect();
+
+    before.symmetric_difference(&after).map(|position| **position).collect()
+
+}
+
+// Flips the given cells and moves the simulation to a generation
+fn apply(game: &mut dyn Simulation, cells: &[(i64, i64)], generation: u64) {
+
+    for position in cells.iter() {
+
+        let state = if game.is_alive(position) {
+            cell::CellState::Dead
+        } else {
+            cell::CellState::Alive
+        };
+
+        // the cells were on the board when the change was recorded
+        let _ = game.set_state(position, state);
+
+    }
+
+    game.set_evolution(generation);
+
+}
+
+
+// tests
+#[cfg(test)]
+use crate::{game, grid, rule};
+
+#[cfg(test)]
+fn live_cells(game: &dyn Simulation) -> Vec<(i64, i64)> {
+    let mut cells = game.bounding_box().map(|region| game.cells_in(&region)).unwrap_or_default();
+    cells.sort();
+    cells
+}
+
+#[cfg(test)]
+fn step(game: &mut dyn Simulation, timeline: &mut Timeline) {
+
+    let before = live_cells(game);
+    let from = game.get_evolution();
+    game.update().unwrap();
+
+    timeline.record(Change { kind: ChangeKind::Step, cells: toggled(&before, &live_cells(game)), from,
+        to: game.get_evolution() });
+
+}
+
+#[cfg(test)]
+fn r_pentomino() -> game::GameOfLife {
+
+    let mut cell_grid = grid::Grid::new((40, 40));
+    for position in [(20, 19), (21, 19), (19, 20), (20, 20), (20, 21)].iter() {
+        cell_grid.set_state(position, cell::CellState::Alive).unwrap();
+    }
+
+    game::GameOfLife::new(cell_grid, rule::Rule::default())
+
+}
+
+#[test]
+fn test_timeline_undo_redo() {
+
+    let mut game = r_pentomino();
+    let mut timeline = Timeline::default();
+
+    let start = live_cells(&game);
+    let mut states = vec![start.clone()];
+    for _ in 0..10 {
+        step(&mut game, &mut timeline);
+        states.push(live_cells(&game));
+    }
+
+    // an edit in two parts on top of the steps
+    game.set_state(&(0, 0), cell::CellState::Alive).unwrap();
+    timeline.record(Change { kind: ChangeKind::Edit, cells: vec![(0, 0)], from: 10, to: 10 });
+    game.set_state(&(1, 0), cell::CellState::Alive).unwrap();
+    timeline.extend_edit(vec![(1, 0)], 10);
+
+    assert_eq!(timeline.undo(&mut game), Some(ChangeKind::Edit));
+    assert_eq!(live_cells(&game), states[10]);
+
+    assert_eq!(timeline.undo(&mut game), Some(ChangeKind::Step));
+    assert_eq!(live_cells(&game), states[9]);
+    assert_eq!(game.get_evolution(), 9);
+
+    assert_eq!(timeline.rewind_to(&mut game, 3), 3);
+    assert_eq!(live_cells(&game), states[3]);
+
+    assert_eq!(timeline.redo(&mut game), Some(ChangeKind::Step));
+    assert_eq!(live_cells(&game), states[4]);
+
+    // stepping after undoing forgets the redo stack, the result matches the first run
+    step(&mut game, &mut timeline);
+    assert!(!timeline.can_redo());
+    assert_eq!(live_cells(&game), states[5]);
+
+    assert_eq!(timeline.rewind_to(&mut game, 0), 0);
+    assert_eq!(live_cells(&game), start);
+    assert_eq!(timeline.undo(&mut game), None);
+
+}
+
+#[test]
+fn test_timeline_budget() {
+
+    let mut game = r_pentomino();
+
+    // room for a few changes only, the oldest ones are forgotten
+    let mut timeline = Timeline::new(1024);
+    for _ in 0..50 {
+        step(&mut game, &mut timeline);
+    }
+
+    assert!(timeline.used() <= 1024);
+    let oldest = timeline.oldest_generation().unwrap();
+    assert!(oldest > 0);
+    assert_eq!(timeline.rewind_to(&mut game, 0), oldest);
+
+    // nothing is kept without a budget
+    let mut timeline = Timeline::new(0);
+    step(&mut game, &mut timeline);
+    assert!(!timeline.can_undo());
+
+}
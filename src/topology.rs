@@ -0,0 +1,320 @@
+
+use std::fmt;
+use std::sync::Arc;
+
+// Describes how a finite grid is glued together at its edges. Positions just off the grid are
+// mapped back onto it, or to None when the space beyond the edge counts as permanently dead
+pub trait Topology: fmt::Debug + Send + Sync {
+
+    fn name(&self) -> &'static str;
+
+    fn resolve(&self, position: (isize, isize), size: (usize, usize)) -> Option<(usize, usize)>;
+
+}
+
+// Cells outside the grid are always dead
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Bounded;
+
+// Opposite edges are glued together
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Torus;
+
+// Left and right edges are glued together, top and bottom edges are glued with a half twist so
+// anything crossing them comes back mirrored in x
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct KleinBottle;
+
+// Projective plane: both pairs of opposite edges are glued with a half twist
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct CrossSurface;
+
+// Every edge acts as a mirror, the cell just beyond an edge is a copy of the cell on it
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Mirror;
+
+impl Topology for Bounded {
+
+    fn name(&self) -> &'static str {
+        "bounded"
+    }
+
+    fn resolve(&self, position: (isize, isize), size: (usize, usize)) -> Option<(usize, usize)> {
+
+        if position.0 < 0 || position.1 < 0 {
+            return None;
+        }
+
+        let (x, y) = (position.0 as usize, position.1 as usize);
+        if x < size.0 && y < size.1 {
+            Some((x, y))
+        } else {
+            None
+        }
+
+    }
+
+}
+
+impl Topology for Torus {
+
+    fn name(&self) -> &'static str {
+        "torus"
+    }
+
+    fn resolve(&self, position: (isize, isize), size: (usize, usize)) -> Option<(usize, usize)> {
+        Some((wrap(position.0, size.0), wrap(position.1, size.1)))
+    }
+
+}
+
+impl Topology for KleinBottle {
+
+    fn name(&self) -> &'static str {
+        "klein"
+    }
+
+    fn resolve(&self, position: (isize, isize), size: (usize, usize)) -> Option<(usize, usize)> {
+
+        let mut x = wrap(position.0, size.0);
+        if crossings(position.1, size.1) % 2 != 0 {
+            x = size.0 - 1 - x;
+        }
+
+        Some((x, wrap(position.1, size.1)))
+
+    }
+
+}
+
+impl Topology for CrossSurface {
+
+    fn name(&self) -> &'static str {
+        "cross-surface"
+    }
+
+    fn resolve(&self, position: (isize, isize), size: (usize, usize)) -> Option<(usize, usize)> {
+
+        let mut x = wrap(position.0, size.0);
+        let mut y = wrap(position.1, size.1);
+
+        if crossings(position.1, size.1) % 2 != 0 {
+            x = size.0 - 1 - x;
+        }
+
+        if crossings(position.0, size.0) % 2 != 0 {
+            y = size.1 - 1 - y;
+        }
+
+        Some((x, y))
+
+    }
+
+}
+
+impl Topology for Mirror {
+
+    fn name(&self) -> &'static str {
+        "mirror"
+    }
+
+    fn resolve(&self, position: (isize, isize), size: (usize, usize)) -> Option<(usize, usize)> {
+        Some((reflect(position.0, size.0), reflect(position.1, size.1)))
+    }
+
+}
+
+// Names accepted by from_name, in the order they are listed to users
+pub const NAMES: [&str; 5] = ["bounded", "torus", "klein", "cross-surface", "mirror"];
+
+// Looks up a topology by the name it reports
+pub fn from_name(name: &str) -> Option<Arc<dyn Topology>> {
+
+    match name {
+        "bounded" => Some(Arc::new(Bounded)),
+        "torus" => Some(Arc::new(Torus)),
+        "klein" => Some(Arc::new(KleinBottle)),
+        "cross-surface" => Some(Arc::new(CrossSurface)),
+        "mirror" => Some(Arc::new(Mirror)),
+        _ => None
+    }
+
+}
+
+// number of times a coordinate has crossed an edge of an axis with the given length
+fn crossings(coordinate: isize, length: usize) -> isize {
+    coordinate.div_euclid(length as isize)
+}
+
+fn wrap(coordinate: isize, length: usize) -> usize {
+    coordinate.rem_euclid(length as isize) as usize
+}
+
+fn reflect(coordinate: isize, length: usize) -> usize {
+
+    let period = 2 * length as isize;
+    let folded = coordinate.rem_euclid(period) as usize;
+
+    if folded < length {
+        folded
+    } else {
+        period as usize - 1 - folded
+    }
+
+}
+
+
+// tests
+#[cfg(test)]
+use std::collections::HashSet;
+#[cfg(test)]
+use crate::{cell, game, grid, rule};
+
+#[cfg(test)]
+fn run_glider(topology: Arc<dyn Topology>, size: (usize, usize), offset: (usize, usize), generations: u32)
+    -> HashSet<(usize, usize)> {
+
+    let mut cell_grid = grid::Grid::with_topology(size, topology);
+
+    let mut seed = std::collections::HashMap::new();
+    for position in [(1, 0), (2, 1), (0, 2), (1, 2), (2, 2)].iter() {
+        seed.entry((position.0 + offset.0, position.1 + offset.1)).or_insert(cell::CellState::Alive);
+    }
+    cell_grid.update(seed);
+
+    let mut game = game::GameOfLife::new(cell_grid, rule::Rule::default());
+    for _ in 0..generations {
+        game.update().unwrap();
+    }
+
+    let mut alive = HashSet::new();
+    for i in 0..size.0 {
+        for j in 0..size.1 {
+            if let Some(cell) = game.cell_grid.get_cell(&(i, j)) {
+                if *cell.get_state() == cell::CellState::Alive {
+                    alive.insert((i, j));
+                }
+            }
+        }
+    }
+
+    alive
+
+}
+
+#[cfg(test)]
+fn cells(positions: &[(usize, usize)]) -> HashSet<(usize, usize)> {
+    positions.iter().cloned().collect()
+}
+
+#[test]
+fn test_topology_resolve() {
+
+    let size = (10, 5);
+
+    assert_eq!(Bounded.resolve((-1, 2), size), None);
+    assert_eq!(Bounded.resolve((3, 5), size), None);
+    assert_eq!(Bounded.resolve((3, 4), size), Some((3, 4)));
+
+    assert_eq!(Torus.resolve((-1, 2), size), Some((9, 2)));
+    assert_eq!(Torus.resolve((10, 5), size), Some((0, 0)));
+
+    assert_eq!(KleinBottle.resolve((-1, 2), size), Some((9, 2)));
+    assert_eq!(KleinBottle.resolve((2, 5), size), Some((7, 0)));
+    assert_eq!(KleinBottle.resolve((2, -1), size), Some((7, 4)));
+
+    assert_eq!(CrossSurface.resolve((-1, 1), size), Some((9, 3)));
+    assert_eq!(CrossSurface.resolve((2, 5), size), Some((7, 0)));
+
+    assert_eq!(Mirror.resolve((-1, 2), size), Some((0, 2)));
+    assert_eq!(Mirror.resolve((10, 5), size), Some((9, 4)));
+
+    for name in NAMES.iter() {
+        assert_eq!(from_name(name).unwrap().name(), *name);
+    }
+    assert!(from_name("sphere").is_none());
+
+}
+
+#[cfg(test)]
+fn mirror_x(positions: &HashSet<(usize, usize)>, width: usize) -> HashSet<(usize, usize)> {
+    positions.iter().map(|(x, y)| (width - 1 - x, *y)).collect()
+}
+
+#[test]
+fn test_topology_glider_away_from_edges() {
+
+    // far from the edges every topology behaves like the infinite plane
+    let expected = cells(&[(6, 5), (7, 6), (5, 7), (6, 7), (7, 7)]);
+    let topologies: Vec<Arc<dyn Topology>> = vec![
+        Arc::new(Bounded), Arc::new(Torus), Arc::new(KleinBottle), Arc::new(CrossSurface), Arc::new(Mirror)
+    ];
+
+    for topology in topologies {
+        assert_eq!(run_glider(topology, (16, 16), (4, 4), 4), expected);
+    }
+
+}
+
+#[test]
+fn test_topology_glider_bounded() {
+
+    // the glider runs into the dead edge and settles into a block
+    let block = cells(&[(20, 6), (20, 7), (21, 6), (21, 7)]);
+    assert_eq!(run_glider(Arc::new(Bounded), (40, 8), (16, 2), 64), block);
+
+}
+
+#[test]
+fn test_topology_glider_torus() {
+
+    // a glider moves one cell diagonally every 4 generations, so after 32 it has gone once around
+    // the 8 cell tall grid and is 8 cells further along in x
+    let start = run_glider(Arc::new(Torus), (40, 8), (16, 2), 0);
+    let shifted: HashSet<(usize, usize)> = start.iter().map(|(x, y)| (x + 8, *y)).collect();
+    assert_eq!(run_glider(Arc::new(Torus), (40, 8), (16, 2), 32), shifted);
+
+}
+
+#[test]
+fn test_topology_glider_klein_bottle() {
+
+    // after crossing the twisted edge the glider is the mirror image of the one on a torus
+    let torus = run_glider(Arc::new(Torus), (40, 8), (16, 2), 24);
+    let klein = run_glider(Arc::new(KleinBottle), (40, 8), (16, 2), 24);
+    assert_eq!(klein, mirror_x(&torus, 40));
+
+}
+
+#[test]
+fn test_topology_glider_cross_surface() {
+
+    // until it reaches the left and right edges a cross-surface behaves like a Klein bottle
+    let klein = run_glider(Arc::new(KleinBottle), (40, 8), (16, 2), 40);
+    let cross_surface = run_glider(Arc::new(CrossSurface), (40, 8), (16, 2), 40);
+    assert_eq!(cross_surface, klein);
+    assert_eq!(cross_surface.len(), 5);
+
+    // crossing the right edge brings it back on the left mirrored in y, heading up instead of down,
+    // where a torus or a Klein bottle only wraps it around
+    let size = (8, 40);
+    let torus = run_glider(Arc::new(Torus), size, (3, 10), 32);
+    let cross_surface = run_glider(Arc::new(CrossSurface), size, (3, 10), 32);
+    let mirrored: HashSet<(usize, usize)> = torus.iter().map(|(x, y)| (*x, size.1 - 1 - y)).collect();
+    assert_eq!(run_glider(Arc::new(KleinBottle), size, (3, 10), 32), torus);
+    assert_eq!(cross_surface, mirrored);
+    assert_ne!(cross_surface, torus);
+    assert_eq!(cross_surface.len(), 5);
+
+}
+
+#[test]
+fn test_topology_glider_mirror() {
+
+    // hitting a mirror is a head-on collision with its own reflection, which leaves two blocks
+    // straddling the edge
+    let blocks = cells(&[(15, 7), (16, 7), (22, 7), (23, 7)]);
+    assert_eq!(run_glider(Arc::new(Mirror), (40, 8), (16, 2), 64), blocks);
+    assert_eq!(run_glider(Arc::new(Mirror), (40, 8), (16, 2), 100), blocks);
+
+}
@@ -0,0 +1,278 @@
+
+use std::collections::{HashMap, HashSet};
+
+use crate::cell;
+use crate::grid;
+use crate::rule;
+use tui::{
+    style::Color,
+    widgets::canvas::{Painter, Shape},
+};
+
+// Smallest rectangle containing every living cell, both corners inclusive
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct BoundingBox {
+    pub min: (i64, i64),
+    pub max: (i64, i64)
+}
+
+impl BoundingBox {
+
+    pub fn from_cells<'a, I>(cells: I) -> Option<Self>
+        where I: IntoIterator<Item = &'a (i64, i64)> {
+
+        let mut bounds: Option<BoundingBox> = None;
+
+        for position in cells {
+            bounds = Some(match bounds {
+                Some(bounds) => bounds.including(position),
+                None => BoundingBox { min: *position, max: *position }
+            });
+        }
+
+        bounds
+
+    }
+
+    pub fn width(&self) -> u64 {
+        (self.max.0 - self.min.0) as u64 + 1
+    }
+
+    pub fn height(&self) -> u64 {
+        (self.max.1 - self.min.1) as u64 + 1
+    }
+
+    pub fn contains(&self, position: &(i64, i64)) -> bool {
+        position.0 >= self.min.0 && position.0 <= self.max.0 &&
+            position.1 >= self.min.1 && position.1 <= self.max.1
+    }
+
+    pub fn including(&self, position: &(i64, i64)) -> Self {
+        BoundingBox {
+            min: (self.min.0.min(position.0), self.min.1.min(position.1)),
+            max: (self.max.0.max(position.0), self.max.1.max(position.1))
+        }
+    }
+
+}
+
+
+// Unbounded 2D universe with signed coordinates. Only living cells are stored, so the universe
+// grows and shrinks with the pattern instead of having a fixed size
+#[derive(Debug, Clone)]
+pub struct Universe {
+    cells: HashSet<(i64, i64)>,
+    color: Color
+}
+
+impl Default for Universe {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Universe {
+
+    pub fn new() -> Self {
+
+        Self {
+            cells: HashSet::new(),
+            color: Color::Cyan
+        }
+
+    }
+
+    pub fn update(&mut self, updated_cells: HashMap<(i64, i64), cell::CellState>) {
+
+        for (position, new_state) in updated_cells.iter() {
+            self.set_state(position, *new_state);
+        }
+
+    }
+
+    pub fn set_state(&mut self, position: &(i64, i64), state: cell::CellState) {
+
+        match state {
+            cell::CellState::Alive => self.cells.insert(*position),
+            cell::CellState::Dead => self.cells.remove(position)
+        };
+
+    }
+
+    pub fn is_alive(&self, position: &(i64, i64)) -> bool {
+        self.cells.contains(position)
+    }
+
+    pub fn get_cell(&self, position: &(i64, i64)) -> cell::CellState {
+
+        if self.is_alive(position) {
+            cell::CellState::Alive
+        } else {
+            cell::CellState::Dead
+        }
+
+    }
+
+    pub fn population(&self) -> usize {
+        self.cells.len()
+    }
+
+    pub fn live_cells(&self) -> Vec<(i64, i64)> {
+        self.cells.iter().cloned().collect()
+    }
+
+    pub fn bounding_box(&self) -> Option<BoundingBox> {
+        BoundingBox::from_cells(self.cells.iter())
+    }
+
+    pub fn clear(&mut self) {
+        self.cells.clear();
+    }
+
+    // Cells alive in the next universe but not in this one, and the other way around
+    pub fn births_and_deaths(&self, next: &Universe) -> (usize, usize) {
+        (next.cells.difference(&self.cells).count(), self.cells.difference(&next.cells).count())
+    }
+
+    // Computes the next generation. Only cells next to a living cell can change, so those are
+    // the only ones visited. Rules with B0 are not supported since they would fill the infinite
+    // empty space in a single generation
+    pub fn step(&self, rule: &rule::Rule) -> Self {
+
+        let mut neighbor_counts: HashMap<(i64, i64), usize> = HashMap::with_capacity(self.cells.len() * 8);
+
+        for (x, y) in self.cells.iter() {
+            for dx in -1..=1 {
+                for dy in -1..=1 {
+                    if dx != 0 || dy != 0 {
+                        *neighbor_counts.entry((x + dx, y + dy)).or_insert(0) += 1;
+                    }
+                }
+            }
+        }
+
+        let mut cells = HashSet::with_capacity(self.cells.len());
+
+        for (position, count) in neighbor_counts.iter() {
+            if rule.next_state(&self.get_cell(position), *count) == cell::CellState::Alive {
+                cells.insert(*position);
+            }
+        }
+
+        // living cells without any living neighbors never made it into the counts
+        if rule.survives(0) {
+            for position in self.cells.iter() {
+                if !neighbor_counts.contains_key(position) {
+                    cells.insert(*position);
+                }
+            }
+        }
+
+        Self {
+            cells,
+            color: self.color
+        }
+
+    }
+
+}
+
+impl From<&grid::Grid> for Universe {
+
+    fn from(cell_grid: &grid::Grid) -> Self {
+
+        let mut universe = Universe::new();
+        for (x, y) in cell_grid.live_cells() {
+            universe.cells.insert((x as i64, y as i64));
+        }
+
+        universe
+
+    }
+
+}
+
+// universes are equal when they contain the same living cells
+impl PartialEq for Universe {
+    fn eq(&self, other: &Self) -> bool {
+        self.cells == other.cells
+    }
+}
+
+// tui-rs integration, cells at negative coordinates fall outside the canvas
+impl Shape for Universe {
+
+    fn draw(&self, painter: &mut Painter) {
+
+        for (x, y) in self.cells.iter() {
+            if *x >= 0 && *y >= 0 {
+                painter.paint(*x as usize, *y as usize, self.color);
+            }
+        }
+
+    }
+
+}
+
+
+// tests
+#[cfg(test)]
+
+#[test]
+fn test_universe_update() {
+
+    let mut universe = Universe::new();
+
+    let mut seed: HashMap<(i64, i64), cell::CellState> = HashMap::new();
+    seed.entry((-3, 5)).or_insert(cell::CellState::Alive);
+    seed.entry((1_000_000, -2)).or_insert(cell::CellState::Alive);
+    universe.update(seed);
+
+    assert_eq!(universe.population(), 2);
+    assert!(universe.is_alive(&(-3, 5)));
+    assert_eq!(universe.get_cell(&(0, 0)), cell::CellState::Dead);
+
+    universe.set_state(&(-3, 5), cell::CellState::Dead);
+    assert_eq!(universe.population(), 1);
+
+}
+
+#[test]
+fn test_universe_bounding_box() {
+
+    let mut universe = Universe::new();
+    assert_eq!(universe.bounding_box(), None);
+
+    universe.set_state(&(-3, 5), cell::CellState::Alive);
+    universe.set_state(&(4, -1), cell::CellState::Alive);
+
+    let bounds = universe.bounding_box().unwrap();
+    assert_eq!(bounds, BoundingBox { min: (-3, -1), max: (4, 5) });
+    assert_eq!((bounds.width(), bounds.height()), (8, 7));
+    assert!(bounds.contains(&(0, 0)));
+    assert!(!bounds.contains(&(5, 0)));
+
+}
+
+#[test]
+fn test_universe_glider_travels() {
+
+    let mut universe = Universe::new();
+    for position in [(1, 0), (2, 1), (0, 2), (1, 2), (2, 2)].iter() {
+        universe.set_state(position, cell::CellState::Alive);
+    }
+
+    let start = universe.bounding_box().unwrap();
+
+    // a glider moves one cell diagonally every 4 generations and never hits an edge
+    let rule = rule::Rule::default();
+    for _ in 0..400 {
+        universe = universe.step(&rule);
+    }
+
+    let bounds = universe.bounding_box().unwrap();
+    assert_eq!(universe.population(), 5);
+    assert_eq!(bounds.min, (start.min.0 + 100, start.min.1 + 100));
+    assert_eq!(bounds.max, (start.max.0 + 100, start.max.1 + 100));
+
+}
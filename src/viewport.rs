@@ -0,0 +1,488 @@
+
+use tui::{
+    layout::Rect,
+    style::Color,
+    widgets::canvas::{Painter, Shape},
+};
+
+use crate::game::Simulation;
+use crate::pattern::Pattern;
+use crate::universe::BoundingBox;
+
+// Closest and farthest zoom levels. At a positive zoom every cell is drawn as a square of
+// 2^zoom x 2^zoom canvas dots, at a negative one every dot shows a square of 2^-zoom x 2^-zoom cells
+pub const MAX_ZOOM: i32 = 3;
+pub const MIN_ZOOM: i32 = -8;
+
+// Largest number of dots across a cell of a pattern preview
+pub const MAX_PREVIEW_SCALE: usize = 4;
+
+// Part of the world shown on the canvas. At zoom 0 each canvas dot shows one cell, and a terminal
+// cell holds 2x4 dots with the braille marker
+#[derive(Debug, Clone, PartialEq)]
+pub struct Viewport {
+    pub origin: (i64, i64),
+    zoom: i32,
+    area: Rect,
+    size: (u64, u64)
+}
+
+impl Default for Viewport {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Viewport {
+
+    pub fn new() -> Self {
+
+        Self {
+            origin: (0, 0),
+            zoom: 0,
+            area: Rect::default(),
+            size: (1, 1)
+        }
+
+    }
+
+    // Fits the viewport to the inside of the canvas, in terminal cells
+    pub fn resize(&mut self, area: Rect) {
+        self.area = area;
+        self.size = ((area.width as u64 * 2).max(1), (area.height as u64 * 4).max(1));
+    }
+
+    // Number of canvas dots across and down
+    pub fn size(&self) -> (u64, u64) {
+        self.size
+    }
+
+    pub fn zoom(&self) -> i32 {
+        self.zoom
+    }
+
+    // Zoom level as a scale, such as "4:1" when a cell is 4 dots wide or "1:4" when a dot is 4
+    // cells wide
+    pub fn scale(&self) -> String {
+        format!("{}:{}", self.dots_per_cell(), self.cells_per_dot())
+    }
+
+    fn cells_per_dot(&self) -> i64 {
+        1 << (-self.zoom).max(0)
+    }
+
+    fn dots_per_cell(&self) -> i64 {
+        1 << self.zoom.max(0)
+    }
+
+    // Cells currently visible
+    pub fn window(&self) -> BoundingBox {
+
+        let cells = |dots: u64| (dots as i64 * self.cells_per_dot() + self.dots_per_cell() - 1) / self.dots_per_cell();
+
+        BoundingBox {
+            min: self.origin,
+            max: (self.origin.0 + cells(self.size.0) - 1, self.origin.1 + cells(self.size.1) - 1)
+        }
+
+    }
+
+    // Top left canvas dot showing a cell, None when the cell is out of view
+    pub fn to_canvas(&self, position: &(i64, i64)) -> Option<(usize, usize)> {
+
+        if !self.window().contains(position) {
+            return None;
+        }
+
+        let dot = |offset: i64| (offset * self.dots_per_cell() / self.cells_per_dot()) as usize;
+        Some((dot(position.0 - self.origin.0), dot(position.1 - self.origin.1)))
+
+    }
+
+    // Top left cell shown by a canvas dot
+    pub fn to_world(&self, dot: (usize, usize)) -> (i64, i64) {
+
+        let offset = |dot: usize| dot as i64 * self.cells_per_dot() / self.dots_per_cell();
+        (self.origin.0 + offset(dot.0), self.origin.1 + offset(dot.1))
+
+    }
+
+    // Number of cells covered by a number of dots, used to pan by whole terminal cells
+    pub fn dots_to_cells(&self, dots: i64) -> i64 {
+        dots * self.cells_per_dot() / self.dots_per_cell()
+    }
+
+    // Cells drawn in a terminal cell of the canvas, None outside of it. Terminal positions are
+    // zero based
+    pub fn cells_under(&self, column: u16, row: u16) -> Option<BoundingBox> {
+
+        let inside = column >= self.area.x && column < self.area.right()
+            && row >= self.area.y && row < self.area.bottom();
+
+        if !inside {
+            return None;
+        }
+
+        let dot = (((column - self.area.x) * 2) as usize, ((row - self.area.y) * 4) as usize);
+        let last = self.to_world((dot.0 + 1, dot.1 + 3));
+        let extra = self.cells_per_dot() - 1;
+
+        Some(BoundingBox {
+            min: self.to_world(dot),
+            max: (last.0 + extra, last.1 + extra)
+        })
+
+    }
+
+    // Cell in the middle of the view
+    pub fn center(&self) -> (i64, i64) {
+
+        let window = self.window();
+        (self.origin.0 + window.width() as i64 / 2, self.origin.1 + window.height() as i64 / 2)
+
+    }
+
+    pub fn center_on(&mut self, position: &(i64, i64)) {
+
+        let window = self.window();
+        self.origin = (position.0 - window.width() as i64 / 2, position.1 - window.height() as i64 / 2);
+
+    }
+
+    // Changes the zoom level, keeping the same cell in the middle of the view
+    pub fn set_zoom(&mut self, zoom: i32) {
+
+        let center = self.center();
+        self.zoom = zoom.clamp(MIN_ZOOM, MAX_ZOOM);
+        self.center_on(&center);
+
+    }
+
+    // Picks the closest zoom that shows the whole region, and centers it
+    pub fn fit(&mut self, region: &BoundingBox) {
+
+        self.zoom = MIN_ZOOM;
+        for zoom in (MIN_ZOOM..=MAX_ZOOM).rev() {
+            self.zoom = zoom;
+            let window = self.window();
+            if window.width() >= region.width() && window.height() >= region.height() {
+                break;
+            }
+        }
+
+        self.center_on(&(region.min.0 + region.width() as i64 / 2, region.min.1 + region.height() as i64 / 2));
+
+    }
+
+    // Scrolls just enough for a cell to come into view
+    pub fn follow(&mut self, position: &(i64, i64)) {
+
+        let window = self.window();
+
+        if position.0 < window.min.0 {
+            self.origin.0 = position.0;
+        } else if position.0 > window.max.0 {
+            self.origin.0 = position.0 - window.width() as i64 + 1;
+        }
+
+        if position.1 < window.min.1 {
+            self.origin.1 = position.1;
+        } else if position.1 > window.max.1 {
+            self.origin.1 = position.1 - window.height() as i64 + 1;
+        }
+
+    }
+
+    // Canvas dots covered by a cell, clipped to the canvas
+    fn dots_of(&self, position: &(i64, i64)) -> Vec<(usize, usize)> {
+
+        let (x, y) = match self.to_canvas(position) {
+            Some(dot) => dot,
+            None => return Vec::new()
+        };
+
+        let side = if self.zoom > 0 { self.dots_per_cell() as usize } else { 1 };
+        let mut dots = Vec::with_capacity(side * side);
+        for dy in 0..side {
+            for dx in 0..side {
+                if ((x + dx) as u64) < self.size.0 && ((y + dy) as u64) < self.size.1 {
+                    dots.push((x + dx, y + dy));
+                }
+            }
+        }
+
+        dots
+
+    }
+
+}
+
+
+// tui-rs integration, draws the visible part of a simulation
+pub struct View<'a> {
+    pub game: &'a dyn Simulation,
+    pub viewport: &'a Viewport,
+    pub color: Color
+}
+
+impl Shape for View<'_> {
+
+    fn draw(&self, painter: &mut Painter) {
+
+        for position in self.game.cells_in(&self.viewport.window()) {
+            for (x, y) in self.viewport.dots_of(&position) {
+                painter.paint(x, y, self.color);
+            }
+        }
+
+    }
+
+}
+
+// Marks a single cell, such as the editing cursor
+pub struct Marker<'a> {
+    pub position: (i64, i64),
+    pub viewport: &'a Viewport,
+    pub color: Color
+}
+
+impl Shape for Marker<'_> {
+
+    fn draw(&self, painter: &mut Painter) {
+
+        for (x, y) in self.viewport.dots_of(&self.position) {
+            painter.paint(x, y, self.color);
+        }
+
+    }
+
+}
+
+// Marks the edge of a rectangle of cells, such as a selection
+pub struct Outline<'a> {
+    pub region: BoundingBox,
+    pub viewport: &'a Viewport,
+    pub color: Color
+}
+
+impl Outline<'_> {
+
+    // Cells on the edge of the region that are in view
+    pub fn cells(&self) -> Vec<(i64, i64)> {
+
+        let window = self.viewport.window();
+        let (min, max) = (self.region.min, self.region.max);
+        let columns = min.0.max(window.min.0)..=max.0.min(window.max.0);
+        let rows = min.1.max(window.min.1)..=max.1.min(window.max.1);
+
+        let mut cells = Vec::new();
+        for y in [min.1, max.1].iter().filter(|y| rows.contains(y)) {
+            cells.extend(columns.clone().map(|x| (x, *y)));
+        }
+        for x in [min.0, max.0].iter().filter(|x| columns.contains(x)) {
+            cells.extend(rows.clone().map(|y| (*x, y)));
+        }
+
+        cells.sort_unstable();
+        cells.dedup();
+        cells
+
+    }
+
+}
+
+impl Shape for Outline<'_> {
+
+    fn draw(&self, painter: &mut Painter) {
+
+        for position in self.cells() {
+            for (x, y) in self.viewport.dots_of(&position) {
+                painter.paint(x, y, self.color);
+            }
+        }
+
+    }
+
+}
+
+// Draws a whole pattern centered in a canvas of the given size in dots, enlarged up to
+// MAX_PREVIEW_SCALE when it is small and shrunk when it does not fit
+pub struct Preview<'a> {
+    pub pattern: &'a Pattern,
+    pub size: (usize, usize),
+    pub color: Color
+}
+
+impl Preview<'_> {
+
+    // Dots to light, every cell becomes a square of dots or several cells share a dot
+    pub fn dots(&self) -> Vec<(usize, usize)> {
+
+        let (width, height) = (self.pattern.size.0.max(1), self.pattern.size.1.max(1));
+        if self.size.0 == 0 || self.size.1 == 0 {
+            return Vec::new();
+        }
+
+        let fits = width <= self.size.0 && height <= self.size.1;
+        let (scale, shrink) = if fits {
+            ((self.size.0 / width).min(self.size.1 / height).min(MAX_PREVIEW_SCALE), 1)
+        } else {
+            (1, width.div_ceil(self.size.0).max(height.div_ceil(self.size.1)))
+        };
+
+        let drawn = |cells: usize| cells.div_ceil(shrink) * scale;
+        let offset = ((self.size.0 - drawn(width)) / 2, (self.size.1 - drawn(height)) / 2);
+
+        let mut dots = Vec::with_capacity(self.pattern.cells.len() * scale * scale);
+        for (x, y) in self.pattern.cells.iter() {
+            let corner = (offset.0 + x / shrink * scale, offset.1 + y / shrink * scale);
+            for dy in 0..scale {
+                for dx in 0..scale {
+                    dots.push((corner.0 + dx, corner.1 + dy));
+                }
+            }
+        }
+
+        dots.sort_unstable();
+        dots.dedup();
+        dots
+
+    }
+
+}
+
+impl Shape for Preview<'_> {
+
+    fn draw(&self, painter: &mut Painter) {
+
+        for (x, y) in self.dots() {
+            painter.paint(x, y, self.color);
+        }
+
+    }
+
+}
+
+
+// tests
+#[cfg(test)]
+
+#[test]
+fn test_viewport_mapping() {
+
+    let mut viewport = Viewport::new();
+    viewport.resize(Rect::new(1, 1, 10, 5));
+    assert_eq!(viewport.size(), (20, 20));
+
+    viewport.origin = (-5, 3);
+    assert_eq!(viewport.to_canvas(&(-5, 3)), Some((0, 0)));
+    assert_eq!(viewport.to_canvas(&(14, 22)), Some((19, 19)));
+    assert_eq!(viewport.to_canvas(&(15, 22)), None);
+    assert_eq!(viewport.to_world((19, 19)), (14, 22));
+
+    // terminal cells hold 2x4 cells, the canvas starts at (1, 1)
+    let under = viewport.cells_under(3, 2).unwrap();
+    assert_eq!((under.min, under.max), ((-1, 7), (0, 10)));
+    assert!(viewport.cells_under(0, 2).is_none());
+    assert!(viewport.cells_under(11, 2).is_none());
+
+}
+
+#[test]
+fn test_viewport_follow() {
+
+    let mut viewport = Viewport::new();
+    viewport.resize(Rect::new(0, 0, 5, 2));
+
+    // cells in view do not scroll
+    viewport.follow(&(9, 7));
+    assert_eq!(viewport.origin, (0, 0));
+
+    viewport.follow(&(10, 8));
+    assert_eq!(viewport.origin, (1, 1));
+
+    viewport.follow(&(-3, 0));
+    assert_eq!(viewport.origin, (-3, 0));
+
+}
+
+#[test]
+fn test_viewport_zoom() {
+
+    let mut viewport = Viewport::new();
+    viewport.resize(Rect::new(0, 0, 8, 4));
+    assert_eq!(viewport.center(), (8, 8));
+
+    // zoomed out every dot covers 4x4 cells, around the same center
+    viewport.set_zoom(-2);
+    assert_eq!(viewport.scale(), "1:4");
+    let window = viewport.window();
+    assert_eq!((window.width(), window.height()), (64, 64));
+    assert_eq!(viewport.center(), (8, 8));
+    assert_eq!(viewport.to_canvas(&(-20, -20)), Some((1, 1)));
+    assert_eq!(viewport.to_world((1, 1)), (-20, -20));
+    let under = viewport.cells_under(0, 0).unwrap();
+    assert_eq!((under.width(), under.height()), (8, 16));
+
+    // zoomed in every cell is 2x2 dots
+    viewport.set_zoom(1);
+    assert_eq!(viewport.scale(), "2:1");
+    assert_eq!(viewport.window().width(), 8);
+    assert_eq!(viewport.dots_of(&viewport.origin.clone()), vec![(0, 0), (1, 0), (0, 1), (1, 1)]);
+
+    viewport.set_zoom(100);
+    assert_eq!(viewport.zoom(), MAX_ZOOM);
+
+}
+
+#[test]
+fn test_viewport_fit() {
+
+    let mut viewport = Viewport::new();
+    viewport.resize(Rect::new(0, 0, 8, 4));
+
+    // a small pattern is shown as large as possible
+    viewport.fit(&BoundingBox { min: (100, 100), max: (102, 102) });
+    assert_eq!(viewport.zoom(), 2);
+    assert!(viewport.window().contains(&(100, 100)) && viewport.window().contains(&(102, 102)));
+
+    // a large one is zoomed out until it fits
+    let region = BoundingBox { min: (-500, 0), max: (499, 99) };
+    viewport.fit(&region);
+    assert_eq!(viewport.zoom(), -6);
+    assert!(viewport.window().contains(&region.min) && viewport.window().contains(&region.max));
+
+}
+
+#[test]
+fn test_preview() {
+
+    // a glider is enlarged as much as allowed and centered
+    let glider = Pattern::from_cells(vec![(1, 0), (2, 1), (0, 2), (1, 2), (2, 2)]);
+    let preview = Preview { pattern: &glider, size: (20, 16), color: Color::White };
+    let dots = preview.dots();
+    assert_eq!(dots.len(), 5 * 16);
+    assert_eq!(dots.iter().min(), Some(&(4, 10)));
+    assert_eq!(dots.iter().max(), Some(&(15, 13)));
+
+    // a row too long for the canvas is shrunk so every dot covers three cells
+    let row = Pattern::from_cells((0..25).map(|x| (x, 0)).collect());
+    let preview = Preview { pattern: &row, size: (10, 4), color: Color::White };
+    assert_eq!(preview.dots(), (0..9).map(|x| (x, 1)).collect::<Vec<_>>());
+
+}
+
+#[test]
+fn test_outline() {
+
+    let mut viewport = Viewport::new();
+    viewport.resize(Rect::new(0, 0, 5, 2));
+
+    // only the edge is drawn, and only the part in view
+    let outline = Outline { region: BoundingBox { min: (2, 1), max: (4, 3) }, viewport: &viewport, color: Color::Yellow };
+    assert_eq!(outline.cells(), vec![(2, 1), (2, 2), (2, 3), (3, 1), (3, 3), (4, 1), (4, 2), (4, 3)]);
+
+    let outline = Outline { region: BoundingBox { min: (-5, 6), max: (20, 30) }, viewport: &viewport, color: Color::Yellow };
+    assert_eq!(outline.cells(), vec![(0, 6), (1, 6), (2, 6), (3, 6), (4, 6), (5, 6), (6, 6), (7, 6), (8, 6), (9, 6)]);
+
+}